@@ -0,0 +1,62 @@
+// compares keyword recognition through Dialect against the per-scanner
+// HashMap<String, TokenType> the scanner used to build; run with
+// `cargo bench --bench keywords`
+use rlox_lib::{Dialect, TokenType, lox_keyword};
+use std::collections::HashMap;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+const ROUNDS: usize = 2_000;
+
+fn words() -> Vec<String> {
+    let source = "class Point < Shape { init(x, y) { this.x = x; this.y = y; } \
+        fun length() { return sqrt(this.x * this.x + this.y * this.y); } } \
+        var total = 0; for (var index = 0; index < count; index = index + 1) { \
+        if (values and index != skip or forced) { total = total + values; } \
+        else { print nil; } } while (true) { super.reset(false); }";
+    source
+        .split(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+        .filter(|word| !word.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+fn hash_map() -> HashMap<String, TokenType> {
+    Dialect::lox()
+        .keywords()
+        .map(|(text, token_type)| (text.to_string(), token_type.clone()))
+        .collect()
+}
+
+fn time(name: &str, words: &[String], lookup: impl Fn(&str) -> Option<TokenType>) -> Duration {
+    let started = Instant::now();
+    let mut found = 0;
+    for _ in 0..ROUNDS {
+        for word in words {
+            if lookup(black_box(word)).is_some() {
+                found += 1;
+            }
+        }
+    }
+    let elapsed = started.elapsed();
+    let lookups = (ROUNDS * words.len()) as f64;
+    println!(
+        "{:<24} {:>8.2} ns/lookup ({} keywords)",
+        name,
+        elapsed.as_nanos() as f64 / lookups,
+        found / ROUNDS
+    );
+    elapsed
+}
+
+fn main() {
+    let words = words();
+    let map = hash_map();
+    let lox = Dialect::lox();
+    let extended = Dialect::default().with_keyword("break", TokenType::Break);
+
+    time("HashMap<String, _>", &words, |word| map.get(word).cloned());
+    time("lox_keyword trie", &words, lox_keyword);
+    time("Dialect::lox()", &words, |word| lox.keyword(word));
+    time("extended Dialect", &words, |word| extended.keyword(word));
+}
@@ -0,0 +1,284 @@
+use crate::span::Span;
+use crate::token::{Literal, Token};
+use std::fmt;
+
+// every node keeps the span of the source it was parsed from; operator and
+// name tokens are kept whole so later stages can report on them
+#[derive(Debug, PartialEq, Clone)]
+pub enum Expr {
+    Literal {
+        value: Literal,
+        span: Span,
+    },
+    Grouping {
+        expression: Box<Expr>,
+        span: Span,
+    },
+    Unary {
+        operator: Token,
+        right: Box<Expr>,
+        span: Span,
+    },
+    Binary {
+        left: Box<Expr>,
+        operator: Token,
+        right: Box<Expr>,
+        span: Span,
+    },
+    // "and" / "or", which only evaluate the right side when they need to
+    Logical {
+        left: Box<Expr>,
+        operator: Token,
+        right: Box<Expr>,
+        span: Span,
+    },
+    Variable {
+        name: Token,
+        span: Span,
+    },
+    Assign {
+        name: Token,
+        value: Box<Expr>,
+        span: Span,
+    },
+    Call {
+        callee: Box<Expr>,
+        // the closing ')', where runtime errors about the call are reported
+        paren: Token,
+        arguments: Vec<Expr>,
+        span: Span,
+    },
+    Get {
+        object: Box<Expr>,
+        name: Token,
+        span: Span,
+    },
+    Set {
+        object: Box<Expr>,
+        name: Token,
+        value: Box<Expr>,
+        span: Span,
+    },
+    This {
+        keyword: Token,
+        span: Span,
+    },
+    Super {
+        keyword: Token,
+        method: Token,
+        span: Span,
+    },
+}
+
+impl Expr {
+    pub fn span(&self) -> Span {
+        match self {
+            Expr::Literal { span, .. }
+            | Expr::Grouping { span, .. }
+            | Expr::Unary { span, .. }
+            | Expr::Binary { span, .. }
+            | Expr::Logical { span, .. }
+            | Expr::Variable { span, .. }
+            | Expr::Assign { span, .. }
+            | Expr::Call { span, .. }
+            | Expr::Get { span, .. }
+            | Expr::Set { span, .. }
+            | Expr::This { span, .. }
+            | Expr::Super { span, .. } => *span,
+        }
+    }
+}
+
+// a Lisp-like rendering that spells out the structure, e.g. (+ 1 (* 2 3))
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Expr::Literal { value, .. } => match value {
+                Literal::String(value) => write!(f, "{:?}", value),
+                Literal::Number(value) => write!(f, "{:?}", value),
+                Literal::Integer(value) => write!(f, "{}", value),
+                Literal::Bool(value) => write!(f, "{}", value),
+                Literal::None => write!(f, "nil"),
+            },
+            Expr::Grouping { expression, .. } => write!(f, "(group {})", expression),
+            Expr::Unary {
+                operator, right, ..
+            } => write!(f, "({} {})", operator.lexeme, right),
+            Expr::Binary {
+                left,
+                operator,
+                right,
+                ..
+            }
+            | Expr::Logical {
+                left,
+                operator,
+                right,
+                ..
+            } => write!(f, "({} {} {})", operator.lexeme, left, right),
+            Expr::Variable { name, .. } => write!(f, "{}", name.lexeme),
+            Expr::Assign { name, value, .. } => write!(f, "(= {} {})", name.lexeme, value),
+            Expr::Call {
+                callee, arguments, ..
+            } => {
+                write!(f, "(call {}", callee)?;
+                for argument in arguments {
+                    write!(f, " {}", argument)?;
+                }
+                write!(f, ")")
+            }
+            Expr::Get { object, name, .. } => write!(f, "(. {} {})", object, name.lexeme),
+            Expr::Set {
+                object,
+                name,
+                value,
+                ..
+            } => write!(f, "(= (. {} {}) {})", object, name.lexeme, value),
+            Expr::This { .. } => write!(f, "this"),
+            Expr::Super { method, .. } => write!(f, "(super {})", method.lexeme),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Stmt {
+    Expression {
+        expression: Expr,
+        span: Span,
+    },
+    Print {
+        expression: Expr,
+        span: Span,
+    },
+    Var {
+        name: Token,
+        initializer: Option<Expr>,
+        span: Span,
+    },
+    Block {
+        statements: Vec<Stmt>,
+        span: Span,
+    },
+    If {
+        condition: Expr,
+        then_branch: Box<Stmt>,
+        else_branch: Option<Box<Stmt>>,
+        span: Span,
+    },
+    // also what a for loop is desugared into
+    While {
+        condition: Expr,
+        body: Box<Stmt>,
+        span: Span,
+    },
+    Function(Function),
+    Return {
+        keyword: Token,
+        value: Option<Expr>,
+        span: Span,
+    },
+    Class {
+        name: Token,
+        // always an Expr::Variable
+        superclass: Option<Expr>,
+        methods: Vec<Function>,
+        span: Span,
+    },
+}
+
+// a function declaration, or a method inside a class body
+#[derive(Debug, PartialEq, Clone)]
+pub struct Function {
+    pub name: Token,
+    pub params: Vec<Token>,
+    pub body: Vec<Stmt>,
+    pub span: Span,
+}
+
+impl Stmt {
+    pub fn span(&self) -> Span {
+        match self {
+            Stmt::Function(function) => function.span,
+            Stmt::Expression { span, .. }
+            | Stmt::Print { span, .. }
+            | Stmt::Var { span, .. }
+            | Stmt::Block { span, .. }
+            | Stmt::If { span, .. }
+            | Stmt::While { span, .. }
+            | Stmt::Return { span, .. }
+            | Stmt::Class { span, .. } => *span,
+        }
+    }
+}
+
+// the same Lisp-like rendering as Expr, e.g. (var a 1.0)
+impl fmt::Display for Stmt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Stmt::Expression { expression, .. } => write!(f, "(; {})", expression),
+            Stmt::Print { expression, .. } => write!(f, "(print {})", expression),
+            Stmt::Var {
+                name, initializer, ..
+            } => match initializer {
+                Some(initializer) => write!(f, "(var {} {})", name.lexeme, initializer),
+                None => write!(f, "(var {})", name.lexeme),
+            },
+            Stmt::Block { statements, .. } => {
+                write!(f, "(block")?;
+                for statement in statements {
+                    write!(f, " {}", statement)?;
+                }
+                write!(f, ")")
+            }
+            Stmt::If {
+                condition,
+                then_branch,
+                else_branch,
+                ..
+            } => match else_branch {
+                Some(else_branch) => {
+                    write!(f, "(if {} {} {})", condition, then_branch, else_branch)
+                }
+                None => write!(f, "(if {} {})", condition, then_branch),
+            },
+            Stmt::While {
+                condition, body, ..
+            } => write!(f, "(while {} {})", condition, body),
+            Stmt::Function(function) => write!(f, "{}", function),
+            Stmt::Return { value, .. } => match value {
+                Some(value) => write!(f, "(return {})", value),
+                None => write!(f, "(return)"),
+            },
+            Stmt::Class {
+                name,
+                superclass,
+                methods,
+                ..
+            } => {
+                write!(f, "(class {}", name.lexeme)?;
+                if let Some(superclass) = superclass {
+                    write!(f, " < {}", superclass)?;
+                }
+                for method in methods {
+                    write!(f, " {}", method)?;
+                }
+                write!(f, ")")
+            }
+        }
+    }
+}
+
+impl fmt::Display for Function {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let params: Vec<&str> = self
+            .params
+            .iter()
+            .map(|param| param.lexeme.as_str())
+            .collect();
+        write!(f, "(fun {} ({})", self.name.lexeme, params.join(" "))?;
+        for statement in &self.body {
+            write!(f, " {}", statement)?;
+        }
+        write!(f, ")")
+    }
+}
@@ -0,0 +1,70 @@
+// what the scanner is in the middle of at a given offset
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum LexMode {
+    Code,
+    // a string part stopped right before "${"
+    InterpolationStart,
+    // inside a string literal, the rest of it comes out as a StringPart
+    String,
+    // inside `depth` nested block comments
+    BlockComment { depth: usize },
+}
+
+// an open "${", with the number of unclosed '{' inside it
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub(crate) struct Interpolation {
+    pub(crate) depth: usize,
+    pub(crate) start: usize,
+    pub(crate) line: usize,
+    pub(crate) column: usize,
+}
+
+// everything a Scanner needs to carry on from one point of its source;
+// taken with Scanner::checkpoint and handed back to Scanner::restore
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Checkpoint {
+    pub(crate) offset: usize,
+    pub(crate) line: usize,
+    pub(crate) column: usize,
+    pub(crate) mode: LexMode,
+    pub(crate) interpolations: Vec<Interpolation>,
+    pub(crate) reached_eof: bool,
+    pub(crate) error_count: usize,
+}
+
+impl Checkpoint {
+    // a starting point chosen by the caller, e.g. the start of a line a
+    // highlighter remembered to be inside a block comment
+    pub fn at(offset: usize, line: usize, column: usize, mode: LexMode) -> Self {
+        Checkpoint {
+            offset,
+            line,
+            column,
+            mode,
+            interpolations: vec![],
+            reached_eof: false,
+            error_count: 0,
+        }
+    }
+
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    pub fn column(&self) -> usize {
+        self.column
+    }
+
+    pub fn mode(&self) -> LexMode {
+        self.mode
+    }
+
+    // how many "${" are open at this point
+    pub fn interpolation_depth(&self) -> usize {
+        self.interpolations.len()
+    }
+}
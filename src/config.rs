@@ -0,0 +1,63 @@
+use crate::dialect::Dialect;
+use std::sync::Arc;
+
+// options controlling how a Scanner reads its source; the default is plain Lox
+#[derive(Debug, PartialEq, Clone)]
+pub struct ScannerConfig {
+    // the keyword table, shared rather than rebuilt for every scanner
+    pub dialect: Arc<Dialect>,
+    // accept identifiers made of any XID_Start / XID_Continue characters
+    // (UAX #31) instead of only ASCII letters, digits and '_'
+    pub unicode_identifiers: bool,
+    // hand out comments as Comment / DocComment tokens rather than skipping them
+    pub emit_comments: bool,
+    // also hand out Whitespace and Newline tokens, so that every byte of the
+    // source lands in exactly one token; implies emit_comments
+    pub lossless: bool,
+    // scan literals without a fraction or exponent as exact Integer tokens;
+    // plain Lox treats every number as an f64
+    pub integer_literals: bool,
+}
+
+impl Default for ScannerConfig {
+    fn default() -> Self {
+        ScannerConfig {
+            dialect: Dialect::lox(),
+            unicode_identifiers: false,
+            emit_comments: false,
+            lossless: false,
+            integer_literals: false,
+        }
+    }
+}
+
+impl ScannerConfig {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn dialect(mut self, dialect: impl Into<Arc<Dialect>>) -> Self {
+        self.dialect = dialect.into();
+        self
+    }
+
+    pub fn unicode_identifiers(mut self, enabled: bool) -> Self {
+        self.unicode_identifiers = enabled;
+        self
+    }
+
+    pub fn emit_comments(mut self, enabled: bool) -> Self {
+        self.emit_comments = enabled;
+        self
+    }
+
+    pub fn lossless(mut self, enabled: bool) -> Self {
+        self.lossless = enabled;
+        self
+    }
+
+    pub fn integer_literals(mut self, enabled: bool) -> Self {
+        self.integer_literals = enabled;
+        self
+    }
+}
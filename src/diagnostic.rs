@@ -0,0 +1,477 @@
+use crate::error::{ParseError, ParseErrorKind, ScanError, ScanErrorKind};
+use crate::source_map::SourceMap;
+use crate::span::Span;
+use crate::token::TokenType;
+use std::fmt;
+use std::fmt::Write;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Severity {
+    Error,
+    Warning,
+    Note,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Error => write!(f, "error"),
+            Severity::Warning => write!(f, "warning"),
+            Severity::Note => write!(f, "note"),
+        }
+    }
+}
+
+// a span worth pointing at; the primary one is what the diagnostic is about,
+// secondary ones add context and get a lighter underline
+#[derive(Debug, PartialEq, Clone)]
+pub struct Label {
+    pub span: Span,
+    pub message: String,
+    pub primary: bool,
+}
+
+impl Label {
+    pub fn primary(span: Span, message: impl Into<String>) -> Self {
+        Label {
+            span,
+            message: message.into(),
+            primary: true,
+        }
+    }
+
+    pub fn secondary(span: Span, message: impl Into<String>) -> Self {
+        Label {
+            span,
+            message: message.into(),
+            primary: false,
+        }
+    }
+}
+
+// a problem found by any stage of the crate, in a shape that can be rendered
+// against the SourceMap the spans point into
+#[derive(Debug, PartialEq, Clone)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub code: Option<String>,
+    pub message: String,
+    pub labels: Vec<Label>,
+    pub notes: Vec<String>,
+    pub help: Option<String>,
+}
+
+impl Diagnostic {
+    pub fn new(severity: Severity, message: impl Into<String>) -> Self {
+        Diagnostic {
+            severity,
+            code: None,
+            message: message.into(),
+            labels: vec![],
+            notes: vec![],
+            help: None,
+        }
+    }
+
+    pub fn error(message: impl Into<String>) -> Self {
+        Self::new(Severity::Error, message)
+    }
+
+    pub fn warning(message: impl Into<String>) -> Self {
+        Self::new(Severity::Warning, message)
+    }
+
+    pub fn code(mut self, code: impl Into<String>) -> Self {
+        self.code = Some(code.into());
+        self
+    }
+
+    pub fn label(mut self, label: Label) -> Self {
+        self.labels.push(label);
+        self
+    }
+
+    pub fn note(mut self, note: impl Into<String>) -> Self {
+        self.notes.push(note.into());
+        self
+    }
+
+    pub fn help(mut self, help: impl Into<String>) -> Self {
+        self.help = Some(help.into());
+        self
+    }
+
+    // the span the diagnostic is about: the first primary label, or else the first label
+    pub fn primary_span(&self) -> Option<Span> {
+        self.labels
+            .iter()
+            .find(|label| label.primary)
+            .or(self.labels.first())
+            .map(|label| label.span)
+    }
+}
+
+// just the header line, for when there is no source to show
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.code {
+            Some(code) => write!(f, "{}[{}]: {}", self.severity, code, self.message),
+            None => write!(f, "{}: {}", self.severity, self.message),
+        }
+    }
+}
+
+impl From<&ScanError> for Diagnostic {
+    fn from(error: &ScanError) -> Self {
+        let label = match &error.kind {
+            ScanErrorKind::UnexpectedCharacter(_) => "not valid in Lox source",
+            ScanErrorKind::UnterminatedString => "string starts here",
+            ScanErrorKind::UnterminatedComment => "comment starts here",
+            ScanErrorKind::UnterminatedInterpolation => "interpolation starts here",
+            ScanErrorKind::InvalidEscape(_) | ScanErrorKind::InvalidUnicodeEscape => {
+                "invalid escape"
+            }
+            ScanErrorKind::MalformedNumber | ScanErrorKind::NumberOutOfRange => "in this literal",
+        };
+        let diagnostic = Diagnostic::error(error.kind.to_string())
+            .code(error.kind.code())
+            .label(Label::primary(error.span, label));
+        match &error.kind {
+            ScanErrorKind::UnterminatedString => diagnostic.help("add a closing '\"'"),
+            ScanErrorKind::UnterminatedComment => {
+                diagnostic.help("every \"/*\" needs a matching \"*/\", nested ones included")
+            }
+            ScanErrorKind::InvalidEscape(_) => {
+                diagnostic.help("valid escapes are \\n, \\t, \\r, \\\\, \\\", \\$ and \\u{XXXX}")
+            }
+            _ => diagnostic,
+        }
+    }
+}
+
+impl From<ScanError> for Diagnostic {
+    fn from(error: ScanError) -> Self {
+        Diagnostic::from(&error)
+    }
+}
+
+impl From<&ParseError> for Diagnostic {
+    fn from(error: &ParseError) -> Self {
+        let label = match &error.kind {
+            ParseErrorKind::Expected { expected, .. } => {
+                format!("expected {}, found {}", expected, error.found)
+            }
+            ParseErrorKind::ExpectedExpression => {
+                format!("expected an expression, found {}", error.found)
+            }
+            ParseErrorKind::InvalidAssignmentTarget => {
+                "cannot assign to what is on the left".to_string()
+            }
+            ParseErrorKind::TooManyArguments => "one argument too many".to_string(),
+            ParseErrorKind::TooManyParameters => "one parameter too many".to_string(),
+            ParseErrorKind::TooMuchNesting => "nested too deeply".to_string(),
+        };
+        let diagnostic = Diagnostic::error(error.kind.to_string())
+            .code(error.kind.code())
+            .label(Label::primary(error.span, label));
+        match error.expected.len() {
+            0 | 1 => diagnostic,
+            _ => {
+                let expected: Vec<String> =
+                    error.expected.iter().map(TokenType::to_string).collect();
+                diagnostic.note(format!("expected one of {}", expected.join(", ")))
+            }
+        }
+    }
+}
+
+impl From<ParseError> for Diagnostic {
+    fn from(error: ParseError) -> Self {
+        Diagnostic::from(&error)
+    }
+}
+
+const RESET: &str = "\x1b[0m";
+const BOLD: &str = "\x1b[1m";
+const BLUE: &str = "\x1b[1;34m";
+const TAB: &str = "    ";
+
+// turns diagnostics into rustc-style text with the offending lines quoted
+// and underlined; plain output leaves out the ANSI colours for CI logs
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Renderer {
+    color: bool,
+}
+
+impl Default for Renderer {
+    fn default() -> Self {
+        Self::plain()
+    }
+}
+
+impl Renderer {
+    pub fn plain() -> Self {
+        Renderer { color: false }
+    }
+
+    pub fn colored() -> Self {
+        Renderer { color: true }
+    }
+
+    pub fn render(&self, diagnostic: &Diagnostic, sources: &SourceMap) -> String {
+        let mut out = String::new();
+        self.write_header(&mut out, diagnostic);
+
+        // labels that resolve, as (line, start column, end column, label),
+        // with columns counted in chars and 0-based
+        let mut lines: Vec<(usize, usize, usize, &Label)> = vec![];
+        let primary = diagnostic.primary_span();
+        let file = primary.and_then(|span| sources.get(span.source));
+        if let (Some(span), Some(file)) = (primary, file) {
+            for label in &diagnostic.labels {
+                if label.span.source != span.source {
+                    continue;
+                }
+                let Some((line, column)) = file.line_column(label.span.start) else {
+                    continue;
+                };
+                let text = file.line(line).unwrap_or("");
+                // a span running over several lines is underlined to the end of the first
+                let end = match file.line_column(label.span.end) {
+                    Some((end_line, end_column)) if end_line == line => end_column,
+                    _ => text.chars().count() + 1,
+                };
+                lines.push((line, column - 1, (end - 1).max(column), label));
+            }
+        }
+        lines.sort_by_key(|&(line, start, _, label)| (line, start, !label.primary));
+
+        let gutter = lines
+            .iter()
+            .map(|&(line, ..)| line.to_string().len())
+            .max()
+            .unwrap_or(0);
+        if let Some(location) = primary.and_then(|span| sources.resolve(span)) {
+            let _ = writeln!(
+                out,
+                "{:gutter$}{} {}:{}:{}",
+                "",
+                self.paint(BLUE, "-->"),
+                location.path.display(),
+                location.line,
+                location.column,
+            );
+        }
+        if let Some(file) = file.filter(|_| !lines.is_empty()) {
+            let _ = writeln!(out, "{:gutter$} {}", "", self.paint(BLUE, "|"));
+            let mut previous = None;
+            for &(line, start, end, label) in &lines {
+                let text = file.line(line).unwrap_or("");
+                if previous != Some(line) {
+                    let number = format!("{:>gutter$}", line);
+                    let _ = writeln!(
+                        out,
+                        "{} {} {}",
+                        self.paint(BLUE, &number),
+                        self.paint(BLUE, "|"),
+                        text.replace('\t', TAB)
+                    );
+                    previous = Some(line);
+                }
+                let indent = display_width(text.chars().take(start));
+                let width = display_width(text.chars().skip(start).take(end - start)).max(1);
+                let marker = if label.primary { "^" } else { "-" };
+                let mut underline = marker.repeat(width);
+                if !label.message.is_empty() {
+                    underline.push(' ');
+                    underline.push_str(&label.message);
+                }
+                let style = match label.primary {
+                    true => self.severity_style(diagnostic.severity),
+                    false => BLUE,
+                };
+                let _ = writeln!(
+                    out,
+                    "{:gutter$} {} {:indent$}{}",
+                    "",
+                    self.paint(BLUE, "|"),
+                    "",
+                    self.paint(style, underline.trim_end()),
+                );
+            }
+        }
+
+        if !diagnostic.notes.is_empty() || diagnostic.help.is_some() {
+            if !lines.is_empty() {
+                let _ = writeln!(out, "{:gutter$} {}", "", self.paint(BLUE, "|"));
+            }
+            for note in &diagnostic.notes {
+                let _ = writeln!(
+                    out,
+                    "{:gutter$} {} note: {}",
+                    "",
+                    self.paint(BLUE, "="),
+                    note
+                );
+            }
+            if let Some(help) = &diagnostic.help {
+                let _ = writeln!(
+                    out,
+                    "{:gutter$} {} help: {}",
+                    "",
+                    self.paint(BLUE, "="),
+                    help
+                );
+            }
+        }
+        out
+    }
+
+    // every diagnostic, separated by a blank line
+    pub fn render_all<'d>(
+        &self,
+        diagnostics: impl IntoIterator<Item = &'d Diagnostic>,
+        sources: &SourceMap,
+    ) -> String {
+        let rendered: Vec<String> = diagnostics
+            .into_iter()
+            .map(|diagnostic| self.render(diagnostic, sources))
+            .collect();
+        rendered.join("\n")
+    }
+
+    fn write_header(&self, out: &mut String, diagnostic: &Diagnostic) {
+        let style = self.severity_style(diagnostic.severity);
+        let severity = match &diagnostic.code {
+            Some(code) => format!("{}[{}]", diagnostic.severity, code),
+            None => diagnostic.severity.to_string(),
+        };
+        let _ = writeln!(
+            out,
+            "{}{}",
+            self.paint(style, &severity),
+            self.paint(BOLD, &format!(": {}", diagnostic.message))
+        );
+    }
+
+    fn severity_style(&self, severity: Severity) -> &'static str {
+        match severity {
+            Severity::Error => "\x1b[1;31m",
+            Severity::Warning => "\x1b[1;33m",
+            Severity::Note => "\x1b[1;32m",
+        }
+    }
+
+    fn paint(&self, style: &str, text: &str) -> String {
+        match self.color {
+            true => format!("{}{}{}", style, text, RESET),
+            false => text.to_string(),
+        }
+    }
+}
+
+// how many columns the chars take up once tabs are expanded
+fn display_width(chars: impl Iterator<Item = char>) -> usize {
+    chars.map(|c| if c == '\t' { TAB.len() } else { 1 }).sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scanner::Scanner;
+
+    #[test]
+    fn renders_scan_errors() {
+        let mut sources = SourceMap::new();
+        let id = sources.add("main.lox", "var a = 1;\nprint a @ 2;\n\t\"open");
+        let mut scanner = Scanner::new(sources.get(id).unwrap().text());
+        scanner.scan_tokens();
+        let diagnostics: Vec<Diagnostic> = scanner.errors().iter().map(Diagnostic::from).collect();
+        let rendered = Renderer::plain().render_all(&diagnostics, &sources);
+        let expected = "\
+error[L0001]: Unexpected character '@'.
+ --> main.lox:2:9
+  |
+2 | print a @ 2;
+  |         ^ not valid in Lox source
+
+error[L0002]: Unterminated string.
+ --> main.lox:3:2
+  |
+3 |     \"open
+  |     ^^^^^ string starts here
+  |
+  = help: add a closing '\"'
+";
+        assert_eq!(rendered, expected);
+    }
+
+    #[test]
+    fn renders_labels_notes_and_colour() {
+        let mut sources = SourceMap::new();
+        sources.add("other.lox", "");
+        let id = sources.add("lib.lox", "fun f(a, a) {\n  return a;\n}");
+        let diagnostic = Diagnostic::warning("Duplicate parameter 'a'.")
+            .code("W0001")
+            .label(Label::primary(
+                Span::new(9, 10).in_source(id),
+                "declared again here",
+            ))
+            .label(Label::secondary(
+                Span::new(6, 7).in_source(id),
+                "first declared here",
+            ))
+            .label(Label::secondary(Span::new(14, 27).in_source(id), ""))
+            .note("parameters share one scope");
+        let expected = "\
+warning[W0001]: Duplicate parameter 'a'.
+ --> lib.lox:1:10
+  |
+1 | fun f(a, a) {
+  |       - first declared here
+  |          ^ declared again here
+2 |   return a;
+  | -----------
+  |
+  = note: parameters share one scope
+";
+        assert_eq!(Renderer::plain().render(&diagnostic, &sources), expected);
+
+        let coloured = Renderer::colored().render(&diagnostic, &sources);
+        assert!(coloured.starts_with("\x1b[1;33mwarning[W0001]\x1b[0m"));
+        assert!(coloured.contains("\x1b[1;34m-->\x1b[0m lib.lox:1:10"));
+    }
+
+    #[test]
+    fn renders_parse_errors() {
+        let mut sources = SourceMap::new();
+        let id = sources.add("expr.lox", "(1 + 2");
+        let tokens = crate::scan(sources.get(id).unwrap().text()).unwrap();
+        let errors = crate::parse_expression(&tokens).unwrap_err();
+        let expected = "\
+error[P0001]: Expect ')' after expression.
+ --> expr.lox:1:7
+  |
+1 | (1 + 2
+  |       ^ expected ')', found end of file
+  |
+  = note: expected one of '(', '.', '/', '*', '-', '+', '>', '>=', '<', '<=', '!=', '==', 'and', 'or', '=', ')'
+";
+        assert_eq!(
+            Renderer::plain().render(&errors[0].clone().into(), &sources),
+            expected
+        );
+    }
+
+    #[test]
+    fn renders_without_source() {
+        let diagnostic = Diagnostic::error("Something went wrong.")
+            .label(Label::primary(Span::new(0, 1), "here"))
+            .help("try again");
+        assert_eq!(diagnostic.to_string(), "error: Something went wrong.");
+        assert_eq!(
+            Renderer::plain().render(&diagnostic, &SourceMap::new()),
+            "error: Something went wrong.\n = help: try again\n"
+        );
+    }
+}
@@ -0,0 +1,220 @@
+use crate::token::TokenType;
+use std::sync::{Arc, LazyLock};
+
+static LOX: LazyLock<Arc<Dialect>> = LazyLock::new(|| Arc::new(Dialect::default()));
+
+// the keyword table a Scanner recognises; built once and shared between
+// scanners through an Arc
+#[derive(Debug, PartialEq, Clone)]
+pub struct Dialect {
+    // whether the plain Lox keywords are part of the table, looked up through
+    // the lox_keyword trie
+    lox_base: bool,
+    // words added, changed or, as None, removed on top of that, bucketed by
+    // byte length and sorted within a bucket; most identifiers find their
+    // bucket empty and go straight on to the trie
+    changes: Vec<Vec<(String, Option<TokenType>)>>,
+}
+
+// the words lox_keyword recognises, in the order of the Lox book
+const LOX_KEYWORDS: [(&str, TokenType); 16] = [
+    ("and", TokenType::And),
+    ("class", TokenType::Class),
+    ("else", TokenType::Else),
+    ("false", TokenType::False),
+    ("for", TokenType::For),
+    ("fun", TokenType::Fun),
+    ("if", TokenType::If),
+    ("nil", TokenType::Nil),
+    ("or", TokenType::Or),
+    ("print", TokenType::Print),
+    ("return", TokenType::Return),
+    ("super", TokenType::Super),
+    ("this", TokenType::This),
+    ("true", TokenType::True),
+    ("var", TokenType::Var),
+    ("while", TokenType::While),
+];
+
+impl Dialect {
+    // the plain Lox keywords, shared by every scanner using the default config
+    pub fn lox() -> Arc<Dialect> {
+        Arc::clone(&LOX)
+    }
+
+    // a dialect without any keywords, every word scans as an Identifier
+    pub fn empty() -> Self {
+        Dialect {
+            lox_base: false,
+            changes: vec![],
+        }
+    }
+
+    // add a keyword or change what an existing one scans as; words without a
+    // dedicated TokenType can use TokenType::Keyword(id)
+    pub fn with_keyword(self, text: &str, token_type: TokenType) -> Self {
+        self.change(text, Some(token_type))
+    }
+
+    pub fn without_keyword(self, text: &str) -> Self {
+        self.change(text, None)
+    }
+
+    pub fn keyword(&self, text: &str) -> Option<TokenType> {
+        if let Some(change) = self.change_of(text) {
+            return change.clone();
+        }
+        match self.lox_base {
+            true => lox_keyword(text),
+            false => None,
+        }
+    }
+
+    pub fn keywords(&self) -> impl Iterator<Item = (&str, &TokenType)> {
+        let lox = LOX_KEYWORDS
+            .iter()
+            .filter(|(text, _)| self.lox_base && self.change_of(text).is_none())
+            .map(|(text, token_type)| (*text, token_type));
+        let changes = self
+            .changes
+            .iter()
+            .flatten()
+            .filter_map(|(text, token_type)| Some((text.as_str(), token_type.as_ref()?)));
+        lox.chain(changes)
+    }
+
+    // record what `text` scans as now, dropping changes that only restate
+    // the base table so an unchanged word keeps going through the trie
+    fn change(mut self, text: &str, token_type: Option<TokenType>) -> Self {
+        let base = match self.lox_base {
+            true => lox_keyword(text),
+            false => None,
+        };
+        if self.changes.len() <= text.len() {
+            self.changes.resize(text.len() + 1, vec![]);
+        }
+        let bucket = &mut self.changes[text.len()];
+        match bucket.binary_search_by(|(word, _)| word.as_str().cmp(text)) {
+            Ok(index) if token_type == base => {
+                bucket.remove(index);
+            }
+            Ok(index) => bucket[index].1 = token_type,
+            Err(_) if token_type == base => {}
+            Err(index) => bucket.insert(index, (text.to_string(), token_type)),
+        }
+        while self.changes.last().is_some_and(Vec::is_empty) {
+            self.changes.pop();
+        }
+        self
+    }
+
+    fn change_of(&self, text: &str) -> Option<&Option<TokenType>> {
+        let bucket = self.changes.get(text.len())?;
+        let index = bucket
+            .binary_search_by(|(word, _)| word.as_str().cmp(text))
+            .ok()?;
+        Some(&bucket[index].1)
+    }
+}
+
+// a fresh copy of the plain Lox keywords, ready to be extended
+impl Default for Dialect {
+    fn default() -> Self {
+        Dialect {
+            lox_base: true,
+            changes: vec![],
+        }
+    }
+}
+
+// the plain Lox keywords as a trie unrolled into matches, like clox's
+// identifierType: branch on the leading bytes, then compare the rest once
+pub fn lox_keyword(text: &str) -> Option<TokenType> {
+    let bytes = text.as_bytes();
+    let rest = |start: usize, rest: &str, token_type: TokenType| {
+        (bytes.get(start..) == Some(rest.as_bytes())).then_some(token_type)
+    };
+    match bytes.first()? {
+        b'a' => rest(1, "nd", TokenType::And),
+        b'c' => rest(1, "lass", TokenType::Class),
+        b'e' => rest(1, "lse", TokenType::Else),
+        b'f' => match bytes.get(1)? {
+            b'a' => rest(2, "lse", TokenType::False),
+            b'o' => rest(2, "r", TokenType::For),
+            b'u' => rest(2, "n", TokenType::Fun),
+            _ => None,
+        },
+        b'i' => rest(1, "f", TokenType::If),
+        b'n' => rest(1, "il", TokenType::Nil),
+        b'o' => rest(1, "r", TokenType::Or),
+        b'p' => rest(1, "rint", TokenType::Print),
+        b'r' => rest(1, "eturn", TokenType::Return),
+        b's' => rest(1, "uper", TokenType::Super),
+        b't' => match bytes.get(1)? {
+            b'h' => rest(2, "is", TokenType::This),
+            b'r' => rest(2, "ue", TokenType::True),
+            _ => None,
+        },
+        b'v' => rest(1, "ar", TokenType::Var),
+        b'w' => rest(1, "hile", TokenType::While),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lox_is_shared() {
+        assert!(Arc::ptr_eq(&Dialect::lox(), &Dialect::lox()));
+        assert_eq!(Dialect::lox().keywords().count(), 16);
+        assert_eq!(Dialect::lox().keyword("while"), Some(TokenType::While));
+        assert_eq!(Dialect::lox().keyword("break"), None);
+    }
+
+    #[test]
+    fn extend_and_remove() {
+        let dialect = Dialect::default()
+            .with_keyword("break", TokenType::Break)
+            .with_keyword("unless", TokenType::Keyword(0))
+            .without_keyword("print");
+        assert_eq!(dialect.keyword("break"), Some(TokenType::Break));
+        assert_eq!(dialect.keyword("unless"), Some(TokenType::Keyword(0)));
+        assert_eq!(dialect.keyword("print"), None);
+        assert_eq!(dialect.keyword("and"), Some(TokenType::And));
+        assert_eq!(dialect.keywords().count(), 17);
+
+        let changed = Dialect::default()
+            .with_keyword("print", TokenType::Keyword(1))
+            .with_keyword("fun", TokenType::Keyword(2))
+            .with_keyword("fun", TokenType::Fun);
+        assert_eq!(changed.keyword("print"), Some(TokenType::Keyword(1)));
+        assert_eq!(changed.keyword("fun"), Some(TokenType::Fun));
+        assert_eq!(changed.keywords().count(), 16);
+        // restoring a word leaves the plain table behind, trie and all
+        assert_eq!(
+            changed.with_keyword("print", TokenType::Print),
+            Dialect::default()
+        );
+        let empty = Dialect::empty().with_keyword("let", TokenType::Var);
+        assert_eq!(empty.keyword("let"), Some(TokenType::Var));
+        assert_eq!(empty.keyword("var"), None);
+        assert_eq!(empty.keywords().count(), 1);
+    }
+
+    #[test]
+    fn trie_matches_table() {
+        let table = Dialect::default().with_keyword("x", TokenType::Keyword(0));
+        for (text, token_type) in Dialect::lox().keywords() {
+            assert_eq!(lox_keyword(text).as_ref(), Some(token_type));
+            assert_eq!(table.keyword(text).as_ref(), Some(token_type));
+        }
+        for text in [
+            "", "a", "an", "andy", "f", "fo", "fort", "t", "th", "thi", "Class", "é",
+        ] {
+            assert_eq!(lox_keyword(text), None, "{}", text);
+            assert_eq!(table.keyword(text), None, "{}", text);
+        }
+    }
+}
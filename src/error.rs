@@ -0,0 +1,210 @@
+use crate::span::Span;
+use crate::token::TokenType;
+use std::error::Error;
+use std::fmt;
+use std::ops::Deref;
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum ScanErrorKind {
+    UnexpectedCharacter(char),
+    UnterminatedString,
+    UnterminatedComment,
+    UnterminatedInterpolation,
+    InvalidEscape(char),
+    InvalidUnicodeEscape,
+    MalformedNumber,
+    NumberOutOfRange,
+}
+
+impl fmt::Display for ScanErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScanErrorKind::UnexpectedCharacter(c) => write!(f, "Unexpected character {:?}.", c),
+            ScanErrorKind::UnterminatedString => write!(f, "Unterminated string."),
+            ScanErrorKind::UnterminatedComment => write!(f, "Unterminated block comment."),
+            ScanErrorKind::UnterminatedInterpolation => {
+                write!(f, "Unterminated string interpolation, expected '}}'.")
+            }
+            ScanErrorKind::InvalidEscape(c) => write!(f, "Invalid escape sequence '\\{}'.", c),
+            ScanErrorKind::InvalidUnicodeEscape => {
+                write!(f, "Invalid unicode escape, expected \\u{{XXXX}}.")
+            }
+            ScanErrorKind::MalformedNumber => write!(f, "Malformed number."),
+            ScanErrorKind::NumberOutOfRange => write!(f, "Number literal out of range."),
+        }
+    }
+}
+
+impl ScanErrorKind {
+    // the stable code for this kind of error, see rlox_lib::explain
+    pub fn code(&self) -> &'static str {
+        match self {
+            ScanErrorKind::UnexpectedCharacter(_) => "L0001",
+            ScanErrorKind::UnterminatedString => "L0002",
+            ScanErrorKind::UnterminatedComment => "L0003",
+            ScanErrorKind::UnterminatedInterpolation => "L0004",
+            ScanErrorKind::InvalidEscape(_) => "L0005",
+            ScanErrorKind::InvalidUnicodeEscape => "L0006",
+            ScanErrorKind::MalformedNumber => "L0007",
+            ScanErrorKind::NumberOutOfRange => "L0008",
+        }
+    }
+}
+
+// line and column are 1-based and point at the start of the span
+#[derive(Debug, PartialEq, Clone)]
+pub struct ScanError {
+    pub kind: ScanErrorKind,
+    pub line: usize,
+    pub column: usize,
+    pub span: Span,
+}
+
+impl ScanError {
+    pub fn message(&self) -> String {
+        self.kind.to_string()
+    }
+}
+
+impl fmt::Display for ScanError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.line, self.column, self.kind)
+    }
+}
+
+impl Error for ScanError {}
+
+// every error found while scanning one source, in source order
+#[derive(Debug, PartialEq, Clone)]
+pub struct ScanErrors {
+    errors: Vec<ScanError>,
+}
+
+impl ScanErrors {
+    pub fn new(errors: Vec<ScanError>) -> Self {
+        ScanErrors { errors }
+    }
+
+    pub fn into_vec(self) -> Vec<ScanError> {
+        self.errors
+    }
+}
+
+impl Deref for ScanErrors {
+    type Target = [ScanError];
+
+    fn deref(&self) -> &[ScanError] {
+        &self.errors
+    }
+}
+
+impl IntoIterator for ScanErrors {
+    type Item = ScanError;
+    type IntoIter = std::vec::IntoIter<ScanError>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.errors.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a ScanErrors {
+    type Item = &'a ScanError;
+    type IntoIter = std::slice::Iter<'a, ScanError>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.errors.iter()
+    }
+}
+
+impl fmt::Display for ScanErrors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, error) in self.errors.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{}", error)?;
+        }
+        Ok(())
+    }
+}
+
+impl Error for ScanErrors {}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum ParseErrorKind {
+    // a particular token had to come next; the message says where, in the
+    // words of the Lox book, e.g. "Expect ')' after arguments."
+    Expected {
+        expected: TokenType,
+        message: &'static str,
+    },
+    ExpectedExpression,
+    InvalidAssignmentTarget,
+    TooManyArguments,
+    TooManyParameters,
+    TooMuchNesting,
+}
+
+impl ParseErrorKind {
+    // the stable code for this kind of error, see rlox_lib::explain
+    pub fn code(&self) -> &'static str {
+        match self {
+            ParseErrorKind::Expected { .. } => "P0001",
+            ParseErrorKind::ExpectedExpression => "P0002",
+            ParseErrorKind::InvalidAssignmentTarget => "P0003",
+            ParseErrorKind::TooManyArguments => "P0004",
+            ParseErrorKind::TooManyParameters => "P0005",
+            ParseErrorKind::TooMuchNesting => "P0006",
+        }
+    }
+}
+
+impl fmt::Display for ParseErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseErrorKind::Expected { message, .. } => write!(f, "{}", message),
+            ParseErrorKind::ExpectedExpression => write!(f, "Expect expression."),
+            ParseErrorKind::InvalidAssignmentTarget => write!(f, "Invalid assignment target."),
+            ParseErrorKind::TooManyArguments => write!(f, "Can't have more than 255 arguments."),
+            ParseErrorKind::TooManyParameters => {
+                write!(f, "Can't have more than 255 parameters.")
+            }
+            ParseErrorKind::TooMuchNesting => write!(f, "Too much nesting."),
+        }
+    }
+}
+
+// found is the token the parser stopped at, the error points at its span;
+// expected lists every token type that would have been accepted there, and is
+// empty for errors that are not about an unexpected token
+#[derive(Debug, PartialEq, Clone)]
+pub struct ParseError {
+    pub kind: ParseErrorKind,
+    pub found: TokenType,
+    pub lexeme: String,
+    pub expected: Vec<TokenType>,
+    pub line: usize,
+    pub column: usize,
+    pub span: Span,
+}
+
+impl ParseError {
+    pub fn message(&self) -> String {
+        self.kind.to_string()
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.found {
+            TokenType::Eof => write!(f, "{}:{}: at end: {}", self.line, self.column, self.kind),
+            _ => write!(
+                f,
+                "{}:{}: at '{}': {}",
+                self.line, self.column, self.lexeme, self.kind
+            ),
+        }
+    }
+}
+
+impl Error for ParseError {}
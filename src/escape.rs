@@ -0,0 +1,124 @@
+use crate::error::ScanErrorKind;
+use std::borrow::Cow;
+
+// decode the escape sequence at the start of `rest`, the text right after a
+// backslash; returns the char and how many bytes of `rest` it used, or the
+// error and how many bytes the malformed sequence covers
+pub(crate) fn decode_escape(rest: &str) -> Result<(char, usize), (ScanErrorKind, usize)> {
+    let c = match rest.chars().next() {
+        Some(c) => c,
+        None => return Err((ScanErrorKind::InvalidEscape('\0'), 0)),
+    };
+    match c {
+        'n' => Ok(('\n', 1)),
+        't' => Ok(('\t', 1)),
+        'r' => Ok(('\r', 1)),
+        '\\' => Ok(('\\', 1)),
+        '"' => Ok(('"', 1)),
+        // "\${" writes a literal "${" instead of starting an interpolation
+        '$' => Ok(('$', 1)),
+        'u' => decode_unicode_escape(rest),
+        c => Err((ScanErrorKind::InvalidEscape(c), c.len_utf8())),
+    }
+}
+
+// \u{XXXX}: one to six hex digits naming a Unicode scalar value
+fn decode_unicode_escape(rest: &str) -> Result<(char, usize), (ScanErrorKind, usize)> {
+    let bytes = rest.as_bytes();
+    if bytes.get(1) != Some(&b'{') {
+        return Err((ScanErrorKind::InvalidUnicodeEscape, 1));
+    }
+    let digits = bytes[2..]
+        .iter()
+        .take_while(|b| b.is_ascii_hexdigit())
+        .count();
+    let close = 2 + digits;
+    if bytes.get(close) != Some(&b'}') {
+        return Err((ScanErrorKind::InvalidUnicodeEscape, close));
+    }
+    let value = match digits {
+        1..=6 => u32::from_str_radix(&rest[2..close], 16).ok(),
+        _ => None,
+    };
+    match value.and_then(char::from_u32) {
+        Some(c) => Ok((c, close + 1)),
+        None => Err((ScanErrorKind::InvalidUnicodeEscape, close + 1)),
+    }
+}
+
+// decode every escape in the body of a string literal; bodies without a
+// backslash are handed back without copying
+pub(crate) fn unescape(body: &str) -> Cow<'_, str> {
+    if !body.contains('\\') {
+        return Cow::Borrowed(body);
+    }
+    let mut value = String::with_capacity(body.len());
+    let mut rest = body;
+    while let Some(index) = rest.find('\\') {
+        value.push_str(&rest[..index]);
+        rest = &rest[index + 1..];
+        match decode_escape(rest) {
+            Ok((c, len)) => {
+                value.push(c);
+                rest = &rest[len..];
+            }
+            // the scanner has already reported it, keep the text as written
+            Err(_) => value.push('\\'),
+        }
+    }
+    value.push_str(rest);
+    Cow::Owned(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn simple_escapes() {
+        assert_eq!(unescape(r#"a\nb\tc\\d\"e\r\${"#), "a\nb\tc\\d\"e\r${");
+    }
+
+    #[test]
+    fn unicode_escapes() {
+        assert_eq!(unescape(r"\u{41}\u{1F600}\u{e9}"), "A😀é");
+        assert_eq!(
+            decode_escape("u{110000}"),
+            Err((ScanErrorKind::InvalidUnicodeEscape, 9))
+        );
+        assert_eq!(
+            decode_escape("u{D800}"),
+            Err((ScanErrorKind::InvalidUnicodeEscape, 7))
+        );
+        assert_eq!(
+            decode_escape("u{}"),
+            Err((ScanErrorKind::InvalidUnicodeEscape, 3))
+        );
+        assert_eq!(
+            decode_escape("u{1234567}"),
+            Err((ScanErrorKind::InvalidUnicodeEscape, 10))
+        );
+        assert_eq!(
+            decode_escape("u{12"),
+            Err((ScanErrorKind::InvalidUnicodeEscape, 4))
+        );
+        assert_eq!(
+            decode_escape("u12"),
+            Err((ScanErrorKind::InvalidUnicodeEscape, 1))
+        );
+    }
+
+    #[test]
+    fn no_escapes_borrows() {
+        assert!(matches!(unescape("plain"), Cow::Borrowed("plain")));
+    }
+
+    #[test]
+    fn invalid_escape_kept() {
+        assert_eq!(
+            decode_escape("q"),
+            Err((ScanErrorKind::InvalidEscape('q'), 1))
+        );
+        assert_eq!(unescape(r"a\qb"), r"a\qb");
+    }
+}
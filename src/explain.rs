@@ -0,0 +1,297 @@
+// long-form explanations for the stable codes diagnostics carry, looked up
+// with rlox_lib::explain("L0001"); codes are never reused once published
+static EXPLANATIONS: &[(&str, &str)] = &[
+    (
+        "L0001",
+        r#"A character that cannot start any Lox token was found.
+
+Lox source is made of identifiers, numbers, strings, comments and a fixed set
+of punctuation. Anything else, such as `@`, `#` or a backtick outside a string
+or comment, is reported and skipped.
+
+Erroneous example:
+
+    var total = price @ 2;
+
+Characters like these are fine inside a string or a comment:
+
+    var total = price * 2; // @ is fine here
+"#,
+    ),
+    (
+        "L0002",
+        r#"A string literal was never closed.
+
+Strings run from one `"` to the next, and may span several lines. When the end
+of the file is reached first, everything from the opening quote on is part of
+the broken string.
+
+Erroneous example:
+
+    print "Hello, world;
+
+Add the closing quote:
+
+    print "Hello, world";
+"#,
+    ),
+    (
+        "L0003",
+        r#"A block comment was never closed.
+
+Block comments nest, so every `/*` needs its own `*/`, including those inside
+an outer comment.
+
+Erroneous example:
+
+    /* outer /* inner */
+    print 1;
+
+Close both comments:
+
+    /* outer /* inner */ */
+    print 1;
+"#,
+    ),
+    (
+        "L0004",
+        r#"A `${` inside a string was never closed by a matching `}`.
+
+Interpolations hold an expression and end at the first `}` that is not paired
+with a `{` inside them.
+
+Erroneous example:
+
+    print "total: ${price * 2";
+
+Close the interpolation before the string ends:
+
+    print "total: ${price * 2}";
+"#,
+    ),
+    (
+        "L0005",
+        r#"A backslash in a string is followed by a character with no escape meaning.
+
+The escapes Lox knows are `\n`, `\t`, `\r`, `\\`, `\"`, `\$` and `\u{...}`.
+
+Erroneous example:
+
+    print "C:\data";
+
+Escape the backslash itself:
+
+    print "C:\\data";
+"#,
+    ),
+    (
+        "L0006",
+        r#"A `\u` escape is not of the form `\u{XXXX}` or does not name a character.
+
+The braces hold one to six hex digits and the value has to be a Unicode scalar
+value, so surrogates such as `D800` and anything above `10FFFF` are rejected.
+
+Erroneous example:
+
+    print "\u00e9";
+
+Put the digits in braces:
+
+    print "\u{00e9}";
+"#,
+    ),
+    (
+        "L0007",
+        r#"A number literal is not well formed.
+
+This covers a radix prefix with no digits (`0x`), digits the radix does not
+allow (`0b102`) and a `_` separator that is not between two digits (`1__000`,
+`100_`).
+
+Erroneous example:
+
+    var mask = 0x;
+
+Give the literal its digits:
+
+    var mask = 0xFF;
+"#,
+    ),
+    (
+        "L0008",
+        r#"A number literal is too large to represent.
+
+Floating point literals have to fit in an f64, and hex, binary and octal
+literals in 64 bits.
+
+Erroneous example:
+
+    var big = 0x1_0000_0000_0000_0000;
+
+Use a smaller value, or a decimal literal when precision does not matter:
+
+    var big = 18446744073709551616.0;
+"#,
+    ),
+    (
+        "P0001",
+        r#"The parser needed a particular token next and found something else.
+
+The message names the missing token and what it should follow, such as a `)`
+after the arguments of a call or a `;` after a statement.
+
+Erroneous example:
+
+    print (1 + 2;
+
+Add what is missing:
+
+    print (1 + 2);
+"#,
+    ),
+    (
+        "P0002",
+        r#"An expression was expected but the next token cannot start one.
+
+Expressions start with a literal, a name, `this`, `super`, `(`, `!` or `-`.
+This usually means an operand is missing on one side of an operator.
+
+Erroneous example:
+
+    var total = price * ;
+
+Give the operator both of its operands:
+
+    var total = price * 2;
+"#,
+    ),
+    (
+        "P0003",
+        r#"The left-hand side of `=` is not something that can be assigned to.
+
+Only variables and properties can be assigned, as in `a = 1` or
+`point.x = 1`. The parser reports the `=` and carries on.
+
+Erroneous example:
+
+    a + b = c;
+
+Assign to a variable instead:
+
+    a = c - b;
+"#,
+    ),
+    (
+        "P0004",
+        r#"A call passes more than 255 arguments.
+
+Lox limits calls to 255 arguments so that an argument count always fits in a
+single byte.
+
+Pass a list or an instance holding the values instead of that many separate
+arguments.
+"#,
+    ),
+    (
+        "P0005",
+        r#"A function or method declares more than 255 parameters.
+
+Like calls, which may pass at most 255 arguments, declarations are limited to
+255 parameters.
+
+Take an instance holding the values instead of that many separate parameters.
+"#,
+    ),
+    (
+        "P0006",
+        r#"Expressions, unary operators or statements are nested too deeply.
+
+The parser gives up after 32 levels of nesting instead of running out of
+stack. Levels include parenthesised and sub-expressions, the right-hand side
+of each `=`, each `!` or `-` applied to an operand, blocks, statement bodies
+and the bodies of functions and methods.
+
+Erroneous example:
+
+    print ((((((((((((((((((((((((((((((((1))))))))))))))))))))))))))))))));
+
+Move inner parts into variables or functions to flatten the code.
+"#,
+    ),
+];
+
+// the explanation for a code, None for codes that do not exist
+pub fn explain(code: &str) -> Option<&'static str> {
+    EXPLANATIONS
+        .iter()
+        .find(|(known, _)| known.eq_ignore_ascii_case(code))
+        .map(|(_, text)| *text)
+}
+
+// every code with an explanation, in order
+pub fn codes() -> impl Iterator<Item = &'static str> {
+    EXPLANATIONS.iter().map(|(code, _)| *code)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::{ParseErrorKind, ScanErrorKind};
+    use crate::token::TokenType;
+
+    #[test]
+    fn every_error_is_explained() {
+        let kinds = [
+            ScanErrorKind::UnexpectedCharacter('@'),
+            ScanErrorKind::UnterminatedString,
+            ScanErrorKind::UnterminatedComment,
+            ScanErrorKind::UnterminatedInterpolation,
+            ScanErrorKind::InvalidEscape('d'),
+            ScanErrorKind::InvalidUnicodeEscape,
+            ScanErrorKind::MalformedNumber,
+            ScanErrorKind::NumberOutOfRange,
+        ];
+        for kind in &kinds {
+            assert!(
+                explain(kind.code()).is_some(),
+                "{} is not explained",
+                kind.code()
+            );
+        }
+        let kinds = [
+            ParseErrorKind::Expected {
+                expected: TokenType::Semicolon,
+                message: "Expect ';' after value.",
+            },
+            ParseErrorKind::ExpectedExpression,
+            ParseErrorKind::InvalidAssignmentTarget,
+            ParseErrorKind::TooManyArguments,
+            ParseErrorKind::TooManyParameters,
+            ParseErrorKind::TooMuchNesting,
+        ];
+        for kind in &kinds {
+            assert!(
+                explain(kind.code()).is_some(),
+                "{} is not explained",
+                kind.code()
+            );
+        }
+        let mut all: Vec<&str> = codes().collect();
+        let count = all.len();
+        all.dedup();
+        assert_eq!(all.len(), count);
+        assert!(all.is_sorted());
+    }
+
+    #[test]
+    fn lookup() {
+        assert!(
+            explain("L0002")
+                .unwrap()
+                .starts_with("A string literal was never closed.")
+        );
+        assert_eq!(explain("l0002"), explain("L0002"));
+        assert_eq!(explain("L9999"), None);
+        assert_eq!(explain(""), None);
+    }
+}
@@ -0,0 +1,260 @@
+use crate::checkpoint::{Checkpoint, LexMode};
+use crate::config::ScannerConfig;
+use crate::error::ScanErrorKind;
+use crate::scanner::Scanner;
+use crate::span::{SourceId, Span};
+use crate::token::{Token, TokenType};
+use std::ops::Range;
+
+// one change to a source text: the bytes in `range` are replaced
+#[derive(Debug, PartialEq, Clone)]
+pub struct TextEdit {
+    pub range: Range<usize>,
+    pub replacement: String,
+}
+
+impl TextEdit {
+    pub fn new(range: Range<usize>, replacement: &str) -> Self {
+        TextEdit {
+            range,
+            replacement: replacement.to_string(),
+        }
+    }
+
+    pub fn apply(&self, source: &str) -> String {
+        let mut edited = source.to_string();
+        edited.replace_range(self.range.clone(), &self.replacement);
+        edited
+    }
+
+    // how far text after the edit moves
+    fn delta(&self) -> isize {
+        self.replacement.len() as isize - self.range.len() as isize
+    }
+}
+
+// the tokens of `new_source`, which is the source `old_tokens` were scanned
+// from with `edit` applied; only the region around the edit is scanned again,
+// the untouched tokens on either side are reused
+pub fn rescan(
+    old_tokens: &[Token],
+    edit: &TextEdit,
+    new_source: &str,
+    config: &ScannerConfig,
+) -> Vec<Token> {
+    let old_len = old_tokens.last().map_or(0, |token| token.span.end);
+    let boundaries = token_boundaries(old_tokens, old_len);
+    // restart after the last token that ends before the edited line: nothing the
+    // scanner looks ahead at crosses a newline, so those tokens cannot change
+    let line_start = new_source[..edit.range.start]
+        .rfind('\n')
+        .map_or(0, |index| index + 1);
+    let restart = (1..old_tokens.len())
+        .rev()
+        .find(|&i| boundaries[i] && old_tokens[i - 1].span.end <= line_start)
+        .unwrap_or(0);
+
+    let mut tokens = old_tokens[..restart].to_vec();
+    let checkpoint = match tokens.last() {
+        Some(last) => {
+            let (line, column) = last.end_position();
+            Checkpoint::at(last.span.end, line, column, LexMode::Code)
+        }
+        None => Checkpoint::at(0, 1, 1, LexMode::Code),
+    };
+    // re-lexed tokens belong to the same file as the ones reused around them
+    let source_id = old_tokens
+        .first()
+        .map_or(SourceId::default(), |token| token.span.source);
+    let mut scanner =
+        Scanner::from_checkpoint(new_source, config.clone(), &checkpoint).source_id(source_id);
+
+    let delta = edit.delta();
+    let unchanged_from = edit.range.start + edit.replacement.len();
+    let mut old = old_tokens.partition_point(|token| token.span.start < edit.range.end);
+    loop {
+        let at_boundary = scanner.at_token_boundary();
+        let token = match scanner.next_token() {
+            Some(token) => Token::from(token),
+            None => return tokens,
+        };
+        while old < old_tokens.len() && shift(old_tokens[old].span.start, delta) < token.span.start
+        {
+            old += 1;
+        }
+        // back in step: the same token at the same shifted place, scanned from
+        // the same state, so everything after it is the old stream moved along
+        if at_boundary && token.span.start >= unchanged_from && old < old_tokens.len() {
+            let candidate = &old_tokens[old];
+            if boundaries[old]
+                && shift(candidate.span.start, delta) == token.span.start
+                && candidate.token_type == token.token_type
+                && candidate.lexeme == token.lexeme
+                && candidate.column == token.column
+            {
+                let line_delta = token.line as isize - candidate.line as isize;
+                tokens.extend(
+                    old_tokens[old..]
+                        .iter()
+                        .map(|token| moved(token, delta, line_delta)),
+                );
+                return tokens;
+            }
+        }
+        tokens.push(token);
+    }
+}
+
+// whether the scanner is in plain code right before each token, so scanning
+// can start over there; `source_len` is the length of the scanned source
+fn token_boundaries(tokens: &[Token], source_len: usize) -> Vec<bool> {
+    let mut boundaries = Vec::with_capacity(tokens.len());
+    let mut depth = 0usize;
+    let mut previous: Option<&Token> = None;
+    for token in tokens {
+        let after_code = previous.is_none_or(|previous| closed(previous, source_len));
+        boundaries
+            .push(depth == 0 && after_code && token.token_type != TokenType::InterpolationStart);
+        match token.token_type {
+            TokenType::InterpolationStart => depth += 1,
+            TokenType::InterpolationEnd => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+        previous = Some(token);
+    }
+    boundaries
+}
+
+// whether the scanner is back in plain code right after `token`: a string
+// part may stop at "${", an interpolation end carries on with the string and
+// an escape error may stand in for either; a token running up to the end of
+// the source or left unterminated would swallow whatever an edit appends
+fn closed(token: &Token, source_len: usize) -> bool {
+    match &token.token_type {
+        TokenType::StringPart | TokenType::InterpolationEnd => false,
+        TokenType::Error(
+            ScanErrorKind::UnterminatedString | ScanErrorKind::UnterminatedComment,
+        ) => false,
+        TokenType::Error(ScanErrorKind::InvalidEscape(_) | ScanErrorKind::InvalidUnicodeEscape) => {
+            token.lexeme.ends_with('"') && token.span.end < source_len
+        }
+        _ => token.span.end < source_len,
+    }
+}
+
+fn shift(offset: usize, delta: isize) -> usize {
+    offset.saturating_add_signed(delta)
+}
+
+fn moved(token: &Token, delta: isize, line_delta: isize) -> Token {
+    Token {
+        line: token.line.saturating_add_signed(line_delta),
+        span: Span {
+            start: shift(token.span.start, delta),
+            end: shift(token.span.end, delta),
+            ..token.span
+        },
+        ..token.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::token::Literal;
+
+    const PROGRAM: &str = "class Greeter {\n  greet(name) {\n    /* say\n hi */ print \"Hello, ${name}! ${ {1} }\";\n    return 1.5e3 + 0x1F;\n  }\n}\n\nvar g = Greeter(); // make one\ng.greet(\"you\");\n";
+
+    fn full_scan(source: &str, config: &ScannerConfig) -> Vec<Token> {
+        Scanner::with_config(source, config.clone()).scan_tokens()
+    }
+
+    fn check(source: &str, edit: &TextEdit, config: &ScannerConfig) {
+        let old_tokens = full_scan(source, config);
+        let new_source = edit.apply(source);
+        assert_eq!(
+            rescan(&old_tokens, edit, &new_source, config),
+            full_scan(&new_source, config),
+            "edit {:?} of {:?}",
+            edit,
+            source
+        );
+    }
+
+    #[test]
+    fn matches_full_scan() {
+        let config = ScannerConfig::default();
+        let edits = [
+            TextEdit::new(0..0, "var x = 1;\n"),
+            TextEdit::new(6..13, "Welcomer"),
+            TextEdit::new(40..41, "\n*/ x /*"),
+            TextEdit::new(62..62, "\""),
+            TextEdit::new(70..71, "}"),
+            TextEdit::new(100..103, "e-"),
+            TextEdit::new(103..103, "2"),
+            TextEdit::new(140..141, ""),
+            TextEdit::new(150..150, "/*"),
+            TextEdit::new(PROGRAM.len()..PROGRAM.len(), "x"),
+        ];
+        for edit in &edits {
+            check(PROGRAM, edit, &config);
+        }
+        // tokens left open at the end of the source, and an escape error
+        // standing in for the string part before "${"
+        check("print \"a\n", &TextEdit::new(9..9, "b"), &config);
+        check("/* a\n", &TextEdit::new(5..5, "*/ x"), &config);
+        check("\"\\q${\nx}\"", &TextEdit::new(6..7, "y"), &config);
+    }
+
+    #[test]
+    fn every_single_character_edit() {
+        let configs = [
+            ScannerConfig::new().emit_comments(true),
+            ScannerConfig::new().lossless(true),
+        ];
+        let chars: Vec<usize> = PROGRAM.char_indices().map(|(i, _)| i).collect();
+        for config in &configs {
+            for &offset in &chars {
+                for replacement in ["", "\"", "\n", "}", "/", "1", " "] {
+                    let end = offset + PROGRAM[offset..].chars().next().unwrap().len_utf8();
+                    check(PROGRAM, &TextEdit::new(offset..end, replacement), config);
+                    check(PROGRAM, &TextEdit::new(offset..offset, replacement), config);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn keeps_the_source_id() {
+        let source_id = SourceId(1);
+        let scan = |source| Scanner::new(source).source_id(source_id).scan_tokens();
+        let edit = TextEdit::new(8..9, "7");
+        let new_source = edit.apply("var a = 1; print a;");
+        let tokens = rescan(
+            &scan("var a = 1; print a;"),
+            &edit,
+            &new_source,
+            &ScannerConfig::default(),
+        );
+        assert_eq!(tokens, scan(&new_source));
+        assert!(tokens.iter().all(|token| token.span.source == source_id));
+    }
+
+    #[test]
+    fn reuses_tokens_after_the_edit() {
+        let config = ScannerConfig::default();
+        let mut old_tokens = full_scan(PROGRAM, &config);
+        // mark a token far from the edit; a full scan would not reproduce it
+        let marked = old_tokens.len() - 3;
+        old_tokens[marked].literal = Literal::Bool(true);
+        let edit = TextEdit::new(6..13, "Hi");
+        let new_source = edit.apply(PROGRAM);
+        let tokens = rescan(&old_tokens, &edit, &new_source, &config);
+        assert_eq!(tokens[1].lexeme, "Hi");
+        assert_eq!(tokens[marked].literal, Literal::Bool(true));
+        assert_eq!(tokens[marked].span.start, old_tokens[marked].span.start - 5);
+        let last = tokens.last().unwrap();
+        assert_eq!(last.token_type, TokenType::Eof);
+        assert_eq!(last.span, Span::new(new_source.len(), new_source.len()));
+    }
+}
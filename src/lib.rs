@@ -1,4 +1,46 @@
-#![allow(dead_code)]
-mod token;
+mod ast;
+mod checkpoint;
+mod config;
+mod diagnostic;
+mod dialect;
+mod error;
+mod escape;
+mod explain;
+mod incremental;
+mod number;
+mod parser;
+#[cfg(feature = "json")]
+mod report;
 mod scanner;
+mod source_map;
+mod span;
+mod stream;
+mod token;
+
+pub use ast::{Expr, Function, Stmt};
+pub use checkpoint::{Checkpoint, LexMode};
+pub use config::ScannerConfig;
+pub use diagnostic::{Diagnostic, Label, Renderer, Severity};
+pub use dialect::{Dialect, lox_keyword};
+pub use error::{ParseError, ParseErrorKind, ScanError, ScanErrorKind, ScanErrors};
+pub use explain::{codes, explain};
+pub use incremental::{TextEdit, rescan};
+pub use parser::{Parser, parse_expression, parse_program};
+#[cfg(feature = "json")]
+pub use report::{JSON_SCHEMA_VERSION, to_json, to_sarif};
+pub use scanner::Scanner;
+pub use source_map::{Location, SourceFile, SourceMap};
+pub use span::{SourceId, Span};
+pub use stream::TokenStream;
+pub use token::{BorrowedToken, Literal, Token, TokenType};
 
+/// Scans `source` into tokens terminated by a single `Eof`, or returns every
+/// error the scanner ran into.
+pub fn scan(source: &str) -> Result<Vec<Token>, ScanErrors> {
+    let mut scanner = Scanner::new(source);
+    let tokens = scanner.scan_tokens();
+    match scanner.errors() {
+        [] => Ok(tokens),
+        errors => Err(ScanErrors::new(errors.to_vec())),
+    }
+}
@@ -0,0 +1,122 @@
+use crate::error::ScanErrorKind;
+
+// the value of a number lexeme: decimal with optional fraction and exponent,
+// or 0x / 0b / 0o integers, any of them with '_' between digits
+pub(crate) fn parse_number(lexeme: &str) -> Result<f64, ScanErrorKind> {
+    let (radix, digits) = split_radix(lexeme);
+    let digits = strip_separators(digits, radix)?;
+    if radix == 10 {
+        match digits.parse::<f64>() {
+            Ok(value) if value.is_infinite() => Err(ScanErrorKind::NumberOutOfRange),
+            Ok(value) => Ok(value),
+            Err(_) => Err(ScanErrorKind::MalformedNumber),
+        }
+    } else {
+        parse_radix_integer(&digits, radix).map(|value| value as f64)
+    }
+}
+
+// an integer lexeme has a radix prefix, or no fraction and no exponent
+pub(crate) fn is_integer_lexeme(lexeme: &str) -> bool {
+    match split_radix(lexeme) {
+        (10, digits) => !digits.contains(['.', 'e', 'E']),
+        _ => true,
+    }
+}
+
+// the exact value of an integer lexeme, which has to fit in an i64
+pub(crate) fn parse_integer(lexeme: &str) -> Result<i64, ScanErrorKind> {
+    let (radix, digits) = split_radix(lexeme);
+    let digits = strip_separators(digits, radix)?;
+    let value = parse_radix_integer(&digits, radix)?;
+    i64::try_from(value).map_err(|_| ScanErrorKind::NumberOutOfRange)
+}
+
+pub(crate) fn split_radix(lexeme: &str) -> (u32, &str) {
+    match lexeme.get(..2) {
+        Some("0x" | "0X") => (16, &lexeme[2..]),
+        Some("0b" | "0B") => (2, &lexeme[2..]),
+        Some("0o" | "0O") => (8, &lexeme[2..]),
+        _ => (10, lexeme),
+    }
+}
+
+pub(crate) fn parse_radix_integer(digits: &str, radix: u32) -> Result<u64, ScanErrorKind> {
+    if digits.is_empty() || !digits.chars().all(|c| c.is_digit(radix)) {
+        return Err(ScanErrorKind::MalformedNumber);
+    }
+    u64::from_str_radix(digits, radix).map_err(|_| ScanErrorKind::NumberOutOfRange)
+}
+
+// every '_' has to sit between two digits of the literal's radix
+pub(crate) fn strip_separators(digits: &str, radix: u32) -> Result<String, ScanErrorKind> {
+    let bytes = digits.as_bytes();
+    let is_digit = |index: Option<usize>| {
+        index
+            .and_then(|i| bytes.get(i))
+            .is_some_and(|&b| (b as char).is_digit(radix))
+    };
+    for (i, &b) in bytes.iter().enumerate() {
+        if b == b'_' && !(is_digit(i.checked_sub(1)) && is_digit(Some(i + 1))) {
+            return Err(ScanErrorKind::MalformedNumber);
+        }
+    }
+    Ok(digits.replace('_', ""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decimal() {
+        assert_eq!(parse_number("123"), Ok(123.0));
+        assert_eq!(parse_number("1_000_000"), Ok(1_000_000.0));
+        assert_eq!(parse_number("1.5e3"), Ok(1500.0));
+        assert_eq!(parse_number("1E-9"), Ok(1e-9));
+        assert_eq!(parse_number("2.5e+2"), Ok(250.0));
+        assert_eq!(parse_number("1e999"), Err(ScanErrorKind::NumberOutOfRange));
+    }
+
+    #[test]
+    fn prefixed() {
+        assert_eq!(parse_number("0xFF"), Ok(255.0));
+        assert_eq!(parse_number("0Xdead_beef"), Ok(3735928559.0));
+        assert_eq!(parse_number("0b1010"), Ok(10.0));
+        assert_eq!(parse_number("0o17"), Ok(15.0));
+        assert_eq!(
+            parse_number("0x1_0000_0000_0000_0000"),
+            Err(ScanErrorKind::NumberOutOfRange)
+        );
+    }
+
+    #[test]
+    fn integers() {
+        assert!(is_integer_lexeme("0x1F"));
+        assert!(is_integer_lexeme("1_000"));
+        assert!(!is_integer_lexeme("1.5"));
+        assert!(!is_integer_lexeme("1e3"));
+        assert_eq!(parse_integer("9007199254740993"), Ok(9007199254740993));
+        assert_eq!(parse_integer("0x7FFF_FFFF_FFFF_FFFF"), Ok(i64::MAX));
+        assert_eq!(
+            parse_integer("0x8000_0000_0000_0000"),
+            Err(ScanErrorKind::NumberOutOfRange)
+        );
+        assert_eq!(
+            parse_integer("99999999999999999999"),
+            Err(ScanErrorKind::NumberOutOfRange)
+        );
+    }
+
+    #[test]
+    fn malformed() {
+        for lexeme in ["0x", "0b102", "0o8", "0xFG", "1_", "1__0", "0x_1", "1_.5"] {
+            assert_eq!(
+                parse_number(lexeme),
+                Err(ScanErrorKind::MalformedNumber),
+                "{}",
+                lexeme
+            );
+        }
+    }
+}
@@ -0,0 +1,1132 @@
+use crate::ast::{Expr, Function, Stmt};
+use crate::error::{ParseError, ParseErrorKind};
+use crate::span::Span;
+use crate::stream::TokenStream;
+use crate::token::{Literal, Token, TokenType};
+use std::borrow::Cow;
+
+// the most arguments a call may pass or a function may take, as in the Lox book
+const MAX_ARGUMENTS: usize = 255;
+
+// how deep expressions, assignments, unary operators, statements and function
+// bodies may nest before the parser gives up rather than overflow the stack;
+// low enough for the 2 MiB stack of a spawned or test thread in an unoptimised
+// build, where a level of parentheses or interpolation costs about 40 KiB
+const MAX_NESTING: usize = 32;
+
+// what the start of a declaration, statement or expression may look like,
+// named in the expected set of an error where one would have fit
+const DECLARATION_START: &[TokenType] = &[TokenType::Class, TokenType::Fun, TokenType::Var];
+const STATEMENT_START: &[TokenType] = &[
+    TokenType::For,
+    TokenType::If,
+    TokenType::Print,
+    TokenType::Return,
+    TokenType::While,
+    TokenType::LeftBrace,
+];
+const EXPRESSION_START: &[TokenType] = &[
+    TokenType::Identifier,
+    TokenType::Number,
+    TokenType::Integer,
+    TokenType::String,
+    TokenType::StringPart,
+    TokenType::True,
+    TokenType::False,
+    TokenType::Nil,
+    TokenType::This,
+    TokenType::Super,
+    TokenType::LeftParen,
+    TokenType::Bang,
+    TokenType::Minus,
+];
+
+// where panic mode stops skipping: a new statement is likely to start here
+const SYNCHRONIZE_AT: &[TokenType] = &[
+    TokenType::Class,
+    TokenType::Fun,
+    TokenType::Var,
+    TokenType::For,
+    TokenType::If,
+    TokenType::While,
+    TokenType::Print,
+    TokenType::Return,
+];
+
+type ParseResult<T> = Result<T, ParseError>;
+
+// a recursive-descent parser over scanner output; Parser::new takes tokens
+// without comment or whitespace trivia, ending in their only Eof, while
+// parse_program and parse_expression accept any scanned tokens
+#[derive(Debug)]
+pub struct Parser<'t> {
+    tokens: TokenStream<'t>,
+    // every error so far; a declaration that fails is skipped in panic mode
+    // and parsing carries on after it
+    errors: Vec<ParseError>,
+    // the token types tried against the current token since the last advance
+    expected: Vec<TokenType>,
+    // how many expressions, assignments, unary operators, statements and
+    // function bodies enclose the current token
+    depth: usize,
+}
+
+impl<'t> Parser<'t> {
+    pub fn new(tokens: &'t [Token]) -> Self {
+        Parser {
+            tokens: TokenStream::new(tokens),
+            errors: vec![],
+            expected: vec![],
+            depth: 0,
+        }
+    }
+
+    pub fn errors(&self) -> &[ParseError] {
+        &self.errors
+    }
+
+    // a single expression that has to make up all of the tokens
+    pub fn parse_expression(&mut self) -> Result<Expr, Vec<ParseError>> {
+        let result = self.expression().and_then(|expr| {
+            self.consume(TokenType::Eof, "Expect end of expression.")?;
+            Ok(expr)
+        });
+        match result {
+            Ok(expr) if self.errors.is_empty() => Ok(expr),
+            Ok(_) => Err(std::mem::take(&mut self.errors)),
+            Err(error) => {
+                self.errors.push(error);
+                Err(std::mem::take(&mut self.errors))
+            }
+        }
+    }
+
+    // program → declaration* EOF, with every error in the file on failure
+    pub fn parse_program(&mut self) -> Result<Vec<Stmt>, Vec<ParseError>> {
+        let mut statements = vec![];
+        while !self.tokens.is_at_end() {
+            statements.extend(self.declaration());
+        }
+        match self.errors.is_empty() {
+            true => Ok(statements),
+            false => Err(std::mem::take(&mut self.errors)),
+        }
+    }
+
+    // a declaration, or None when it had a syntax error; the error is recorded
+    // and the tokens up to the next likely statement boundary are skipped
+    fn declaration(&mut self) -> Option<Stmt> {
+        match self.declaration_or_error() {
+            Ok(statement) => Some(statement),
+            Err(error) => {
+                self.errors.push(error);
+                self.synchronize();
+                None
+            }
+        }
+    }
+
+    fn synchronize(&mut self) {
+        self.advance();
+        while !self.tokens.is_at_end() {
+            if self.tokens.previous().map(|token| &token.token_type) == Some(&TokenType::Semicolon)
+                || SYNCHRONIZE_AT.contains(&self.tokens.peek().token_type)
+            {
+                return;
+            }
+            self.advance();
+        }
+    }
+
+    // declaration → classDecl | funDecl | varDecl | statement
+    fn declaration_or_error(&mut self) -> ParseResult<Stmt> {
+        let token = self.tokens.peek();
+        match token.token_type {
+            TokenType::Class => {
+                self.advance();
+                self.class_declaration(token)
+            }
+            TokenType::Fun => {
+                self.advance();
+                Ok(Stmt::Function(self.function(token.span, false)?))
+            }
+            TokenType::Var => {
+                self.advance();
+                self.var_declaration(token)
+            }
+            _ => {
+                self.expect(DECLARATION_START);
+                self.statement()
+            }
+        }
+    }
+
+    // classDecl → "class" IDENTIFIER ( "<" IDENTIFIER )? "{" function* "}"
+    fn class_declaration(&mut self, keyword: &Token) -> ParseResult<Stmt> {
+        let name = self.consume(TokenType::Identifier, "Expect class name.")?;
+        let mut superclass = None;
+        if self.next_if(&[TokenType::Less]).is_some() {
+            let name = self.consume(TokenType::Identifier, "Expect superclass name.")?;
+            superclass = Some(Expr::Variable {
+                name: name.clone(),
+                span: name.span,
+            });
+        }
+        self.consume(TokenType::LeftBrace, "Expect '{' before class body.")?;
+        let mut methods = vec![];
+        while !self.check(&TokenType::RightBrace) && !self.tokens.is_at_end() {
+            let start = self.tokens.peek().span;
+            methods.push(self.function(start, true)?);
+        }
+        self.consume(TokenType::RightBrace, "Expect '}' after class body.")?;
+        Ok(Stmt::Class {
+            name: name.clone(),
+            superclass,
+            methods,
+            span: self.span_from(keyword.span),
+        })
+    }
+
+    // function → IDENTIFIER "(" parameters? ")" block, after "fun" unless a method
+    fn function(&mut self, start: Span, method: bool) -> ParseResult<Function> {
+        let (name_message, paren_message, body_message) = match method {
+            true => (
+                "Expect method name.",
+                "Expect '(' after method name.",
+                "Expect '{' before method body.",
+            ),
+            false => (
+                "Expect function name.",
+                "Expect '(' after function name.",
+                "Expect '{' before function body.",
+            ),
+        };
+        let name = self.consume(TokenType::Identifier, name_message)?;
+        self.consume(TokenType::LeftParen, paren_message)?;
+        let mut params = vec![];
+        if !self.check(&TokenType::RightParen) {
+            loop {
+                if params.len() == MAX_ARGUMENTS {
+                    let error =
+                        self.error_at(self.tokens.peek(), ParseErrorKind::TooManyParameters);
+                    self.errors.push(error);
+                }
+                params.push(
+                    self.consume(TokenType::Identifier, "Expect parameter name.")?
+                        .clone(),
+                );
+                if self.next_if(&[TokenType::Comma]).is_none() {
+                    break;
+                }
+            }
+        }
+        self.consume(TokenType::RightParen, "Expect ')' after parameters.")?;
+        self.consume(TokenType::LeftBrace, body_message)?;
+        let body = self.nested(Self::block)?;
+        Ok(Function {
+            name: name.clone(),
+            params,
+            body,
+            span: self.span_from(start),
+        })
+    }
+
+    // varDecl → "var" IDENTIFIER ( "=" expression )? ";"
+    fn var_declaration(&mut self, keyword: &Token) -> ParseResult<Stmt> {
+        let name = self.consume(TokenType::Identifier, "Expect variable name.")?;
+        let initializer = match self.next_if(&[TokenType::Equal]) {
+            Some(_) => Some(self.expression()?),
+            None => None,
+        };
+        self.consume(
+            TokenType::Semicolon,
+            "Expect ';' after variable declaration.",
+        )?;
+        Ok(Stmt::Var {
+            name: name.clone(),
+            initializer,
+            span: self.span_from(keyword.span),
+        })
+    }
+
+    // statement → exprStmt | forStmt | ifStmt | printStmt | returnStmt | whileStmt | block
+    fn statement(&mut self) -> ParseResult<Stmt> {
+        let token = self.tokens.peek();
+        let statement = match token.token_type {
+            TokenType::For => Self::for_statement,
+            TokenType::If => Self::if_statement,
+            TokenType::Print => Self::print_statement,
+            TokenType::Return => Self::return_statement,
+            TokenType::While => Self::while_statement,
+            TokenType::LeftBrace => |parser: &mut Self, token: &Token| {
+                Ok(Stmt::Block {
+                    statements: parser.block()?,
+                    span: parser.span_from(token.span),
+                })
+            },
+            _ => {
+                self.expect(STATEMENT_START);
+                return self.expression_statement();
+            }
+        };
+        self.nested(|parser| {
+            parser.advance();
+            statement(parser, token)
+        })
+    }
+
+    // for (init; condition; increment) body becomes
+    // { init; while (condition) { body; increment; } }
+    fn for_statement(&mut self, keyword: &Token) -> ParseResult<Stmt> {
+        self.consume(TokenType::LeftParen, "Expect '(' after 'for'.")?;
+        let initializer = match self.tokens.peek().token_type {
+            TokenType::Semicolon => {
+                self.advance();
+                None
+            }
+            TokenType::Var => {
+                let var = self.advance();
+                Some(self.var_declaration(var)?)
+            }
+            _ => {
+                self.expect(&[TokenType::Semicolon, TokenType::Var]);
+                Some(self.expression_statement()?)
+            }
+        };
+        let condition = match self.check(&TokenType::Semicolon) {
+            true => None,
+            false => Some(self.expression()?),
+        };
+        self.consume(TokenType::Semicolon, "Expect ';' after loop condition.")?;
+        let increment = match self.check(&TokenType::RightParen) {
+            true => None,
+            false => Some(self.expression()?),
+        };
+        self.consume(TokenType::RightParen, "Expect ')' after for clauses.")?;
+        let mut body = self.statement()?;
+        let span = self.span_from(keyword.span);
+
+        if let Some(increment) = increment {
+            body = Stmt::Block {
+                span: body.span(),
+                statements: vec![
+                    body,
+                    Stmt::Expression {
+                        span: increment.span(),
+                        expression: increment,
+                    },
+                ],
+            };
+        }
+        // a missing condition loops forever, the literal takes the span of "for"
+        let condition = condition.unwrap_or(Expr::Literal {
+            value: Literal::Bool(true),
+            span: keyword.span,
+        });
+        body = Stmt::While {
+            condition,
+            body: Box::new(body),
+            span,
+        };
+        if let Some(initializer) = initializer {
+            body = Stmt::Block {
+                statements: vec![initializer, body],
+                span,
+            };
+        }
+        Ok(body)
+    }
+
+    fn if_statement(&mut self, keyword: &Token) -> ParseResult<Stmt> {
+        self.consume(TokenType::LeftParen, "Expect '(' after 'if'.")?;
+        let condition = self.expression()?;
+        self.consume(TokenType::RightParen, "Expect ')' after if condition.")?;
+        let then_branch = Box::new(self.statement()?);
+        // a dangling else belongs to the nearest if
+        let else_branch = match self.next_if(&[TokenType::Else]) {
+            Some(_) => Some(Box::new(self.statement()?)),
+            None => None,
+        };
+        Ok(Stmt::If {
+            condition,
+            then_branch,
+            else_branch,
+            span: self.span_from(keyword.span),
+        })
+    }
+
+    fn print_statement(&mut self, keyword: &Token) -> ParseResult<Stmt> {
+        let expression = self.expression()?;
+        self.consume(TokenType::Semicolon, "Expect ';' after value.")?;
+        Ok(Stmt::Print {
+            expression,
+            span: self.span_from(keyword.span),
+        })
+    }
+
+    fn return_statement(&mut self, keyword: &Token) -> ParseResult<Stmt> {
+        let value = match self.check(&TokenType::Semicolon) {
+            true => None,
+            false => Some(self.expression()?),
+        };
+        self.consume(TokenType::Semicolon, "Expect ';' after return value.")?;
+        Ok(Stmt::Return {
+            keyword: keyword.clone(),
+            value,
+            span: self.span_from(keyword.span),
+        })
+    }
+
+    fn while_statement(&mut self, keyword: &Token) -> ParseResult<Stmt> {
+        self.consume(TokenType::LeftParen, "Expect '(' after 'while'.")?;
+        let condition = self.expression()?;
+        self.consume(TokenType::RightParen, "Expect ')' after condition.")?;
+        let body = self.statement()?;
+        Ok(Stmt::While {
+            condition,
+            body: Box::new(body),
+            span: self.span_from(keyword.span),
+        })
+    }
+
+    // the statements of a block whose '{' has been consumed, up to its '}'
+    fn block(&mut self) -> ParseResult<Vec<Stmt>> {
+        let mut statements = vec![];
+        while !self.check(&TokenType::RightBrace) && !self.tokens.is_at_end() {
+            statements.extend(self.declaration());
+        }
+        self.consume(TokenType::RightBrace, "Expect '}' after block.")?;
+        Ok(statements)
+    }
+
+    fn expression_statement(&mut self) -> ParseResult<Stmt> {
+        let expression = self.expression()?;
+        self.consume(TokenType::Semicolon, "Expect ';' after expression.")?;
+        Ok(Stmt::Expression {
+            span: self.span_from(expression.span()),
+            expression,
+        })
+    }
+
+    // from `start` to the end of the last token consumed
+    fn span_from(&self, start: Span) -> Span {
+        match self.tokens.previous() {
+            Some(previous) => start.to(previous.span),
+            None => start,
+        }
+    }
+
+    // expression → assignment
+    fn expression(&mut self) -> ParseResult<Expr> {
+        self.nested(Self::assignment)
+    }
+
+    // assignment → ( call "." )? IDENTIFIER "=" assignment | logic_or
+    fn assignment(&mut self) -> ParseResult<Expr> {
+        let expr = self.or()?;
+        let Some(equals) = self.next_if(&[TokenType::Equal]) else {
+            return Ok(expr);
+        };
+        let value = self.nested(Self::assignment)?;
+        let span = expr.span().to(value.span());
+        match expr {
+            Expr::Variable { name, .. } => Ok(Expr::Assign {
+                name,
+                value: Box::new(value),
+                span,
+            }),
+            Expr::Get { object, name, .. } => Ok(Expr::Set {
+                object,
+                name,
+                value: Box::new(value),
+                span,
+            }),
+            // reported, but the parser is not confused, so carry on
+            expr => {
+                let error = self.error_at(equals, ParseErrorKind::InvalidAssignmentTarget);
+                self.errors.push(error);
+                Ok(expr)
+            }
+        }
+    }
+
+    fn or(&mut self) -> ParseResult<Expr> {
+        self.logical(&[TokenType::Or], Self::and)
+    }
+
+    fn and(&mut self) -> ParseResult<Expr> {
+        self.logical(&[TokenType::And], Self::equality)
+    }
+
+    fn equality(&mut self) -> ParseResult<Expr> {
+        self.binary(
+            &[TokenType::BangEqual, TokenType::EqualEqual],
+            Self::comparison,
+        )
+    }
+
+    fn comparison(&mut self) -> ParseResult<Expr> {
+        self.binary(
+            &[
+                TokenType::Greater,
+                TokenType::GreaterEqual,
+                TokenType::Less,
+                TokenType::LessEqual,
+            ],
+            Self::term,
+        )
+    }
+
+    fn term(&mut self) -> ParseResult<Expr> {
+        self.binary(&[TokenType::Minus, TokenType::Plus], Self::factor)
+    }
+
+    fn factor(&mut self) -> ParseResult<Expr> {
+        self.binary(&[TokenType::Slash, TokenType::Star], Self::unary)
+    }
+
+    // a left-associative run of `operand (operator operand)*`
+    fn binary(
+        &mut self,
+        operators: &[TokenType],
+        operand: fn(&mut Self) -> ParseResult<Expr>,
+    ) -> ParseResult<Expr> {
+        let mut expr = operand(self)?;
+        while let Some(operator) = self.next_if(operators) {
+            let right = operand(self)?;
+            expr = Expr::Binary {
+                span: expr.span().to(right.span()),
+                left: Box::new(expr),
+                operator: operator.clone(),
+                right: Box::new(right),
+            };
+        }
+        Ok(expr)
+    }
+
+    fn logical(
+        &mut self,
+        operators: &[TokenType],
+        operand: fn(&mut Self) -> ParseResult<Expr>,
+    ) -> ParseResult<Expr> {
+        let mut expr = operand(self)?;
+        while let Some(operator) = self.next_if(operators) {
+            let right = operand(self)?;
+            expr = Expr::Logical {
+                span: expr.span().to(right.span()),
+                left: Box::new(expr),
+                operator: operator.clone(),
+                right: Box::new(right),
+            };
+        }
+        Ok(expr)
+    }
+
+    // unary → ( "!" | "-" ) unary | call
+    fn unary(&mut self) -> ParseResult<Expr> {
+        match self.next_if(&[TokenType::Bang, TokenType::Minus]) {
+            Some(operator) => {
+                let right = self.nested(Self::unary)?;
+                Ok(Expr::Unary {
+                    span: operator.span.to(right.span()),
+                    operator: operator.clone(),
+                    right: Box::new(right),
+                })
+            }
+            None => self.call(),
+        }
+    }
+
+    // call → primary ( "(" arguments? ")" | "." IDENTIFIER )*
+    fn call(&mut self) -> ParseResult<Expr> {
+        let mut expr = self.primary()?;
+        loop {
+            if self.next_if(&[TokenType::LeftParen]).is_some() {
+                expr = self.finish_call(expr)?;
+            } else if self.next_if(&[TokenType::Dot]).is_some() {
+                let name =
+                    self.consume(TokenType::Identifier, "Expect property name after '.'.")?;
+                expr = Expr::Get {
+                    span: expr.span().to(name.span),
+                    object: Box::new(expr),
+                    name: name.clone(),
+                };
+            } else {
+                return Ok(expr);
+            }
+        }
+    }
+
+    fn finish_call(&mut self, callee: Expr) -> ParseResult<Expr> {
+        let mut arguments = vec![];
+        if !self.check(&TokenType::RightParen) {
+            loop {
+                if arguments.len() == MAX_ARGUMENTS {
+                    let error = self.error_at(self.tokens.peek(), ParseErrorKind::TooManyArguments);
+                    self.errors.push(error);
+                }
+                arguments.push(self.expression()?);
+                if self.next_if(&[TokenType::Comma]).is_none() {
+                    break;
+                }
+            }
+        }
+        let paren = self.consume(TokenType::RightParen, "Expect ')' after arguments.")?;
+        Ok(Expr::Call {
+            span: callee.span().to(paren.span),
+            callee: Box::new(callee),
+            paren: paren.clone(),
+            arguments,
+        })
+    }
+
+    fn primary(&mut self) -> ParseResult<Expr> {
+        let token = self.tokens.peek();
+        let literal = |value| Expr::Literal {
+            value,
+            span: token.span,
+        };
+        let expr = match token.token_type {
+            TokenType::False => literal(Literal::Bool(false)),
+            TokenType::True => literal(Literal::Bool(true)),
+            TokenType::Nil => literal(Literal::None),
+            TokenType::Number | TokenType::Integer | TokenType::String => {
+                literal(token.literal.clone())
+            }
+            TokenType::StringPart => {
+                self.advance();
+                return self.interpolation(token);
+            }
+            TokenType::This => Expr::This {
+                keyword: token.clone(),
+                span: token.span,
+            },
+            TokenType::Identifier => Expr::Variable {
+                name: token.clone(),
+                span: token.span,
+            },
+            TokenType::Super => {
+                self.advance();
+                self.consume(TokenType::Dot, "Expect '.' after 'super'.")?;
+                let method =
+                    self.consume(TokenType::Identifier, "Expect superclass method name.")?;
+                return Ok(Expr::Super {
+                    keyword: token.clone(),
+                    method: method.clone(),
+                    span: token.span.to(method.span),
+                });
+            }
+            TokenType::LeftParen => {
+                self.advance();
+                let expression = self.expression()?;
+                let paren = self.consume(TokenType::RightParen, "Expect ')' after expression.")?;
+                return Ok(Expr::Grouping {
+                    expression: Box::new(expression),
+                    span: token.span.to(paren.span),
+                });
+            }
+            _ => {
+                self.expect(EXPRESSION_START);
+                return Err(self.unexpected(ParseErrorKind::ExpectedExpression));
+            }
+        };
+        self.advance();
+        Ok(expr)
+    }
+
+    // "a ${b} c" becomes ("a " + b) + " c"; the '+' takes the span of the "${"
+    fn interpolation(&mut self, first: &'t Token) -> ParseResult<Expr> {
+        let mut expr = Expr::Literal {
+            value: first.literal.clone(),
+            span: first.span,
+        };
+        while let Some(start) = self.next_if(&[TokenType::InterpolationStart]) {
+            let value = self.expression()?;
+            self.consume(
+                TokenType::InterpolationEnd,
+                "Expect '}' after interpolated expression.",
+            )?;
+            let part = self.consume(
+                TokenType::StringPart,
+                "Expect rest of string after interpolation.",
+            )?;
+            let plus = Token {
+                token_type: TokenType::Plus,
+                lexeme: "+".to_string(),
+                literal: Literal::None,
+                ..start.clone()
+            };
+            let part = Expr::Literal {
+                value: part.literal.clone(),
+                span: part.span,
+            };
+            for right in [value, part] {
+                expr = Expr::Binary {
+                    span: expr.span().to(right.span()),
+                    left: Box::new(expr),
+                    operator: plus.clone(),
+                    right: Box::new(right),
+                };
+            }
+        }
+        Ok(expr)
+    }
+
+    // parse one level deeper, failing at the current token once the input
+    // nests past MAX_NESTING
+    fn nested<T>(&mut self, parse: impl FnOnce(&mut Self) -> ParseResult<T>) -> ParseResult<T> {
+        if self.depth == MAX_NESTING {
+            return Err(self.error_at(self.tokens.peek(), ParseErrorKind::TooMuchNesting));
+        }
+        self.depth += 1;
+        let result = parse(self);
+        self.depth -= 1;
+        result
+    }
+
+    fn consume(&mut self, token_type: TokenType, message: &'static str) -> ParseResult<&'t Token> {
+        match self.check(&token_type) {
+            true => Ok(self.advance()),
+            false => Err(self.unexpected(ParseErrorKind::Expected {
+                expected: token_type,
+                message,
+            })),
+        }
+    }
+
+    fn advance(&mut self) -> &'t Token {
+        self.expected.clear();
+        self.tokens.advance()
+    }
+
+    fn check(&mut self, token_type: &TokenType) -> bool {
+        let found = self.tokens.check(token_type);
+        if !found {
+            self.expect(std::slice::from_ref(token_type));
+        }
+        found
+    }
+
+    fn next_if(&mut self, token_types: &[TokenType]) -> Option<&'t Token> {
+        match self.tokens.next_if(token_types) {
+            Some(token) => {
+                self.expected.clear();
+                Some(token)
+            }
+            None => {
+                self.expect(token_types);
+                None
+            }
+        }
+    }
+
+    // note token types that would have been accepted at the current token
+    fn expect(&mut self, token_types: &[TokenType]) {
+        for token_type in token_types {
+            if !self.expected.contains(token_type) {
+                self.expected.push(token_type.clone());
+            }
+        }
+    }
+
+    // the current token is not one the grammar allows here
+    fn unexpected(&self, kind: ParseErrorKind) -> ParseError {
+        ParseError {
+            expected: self.expected.clone(),
+            ..self.error_at(self.tokens.peek(), kind)
+        }
+    }
+
+    fn error_at(&self, token: &Token, kind: ParseErrorKind) -> ParseError {
+        ParseError {
+            kind,
+            found: token.token_type.clone(),
+            lexeme: token.lexeme.clone(),
+            expected: vec![],
+            line: token.line,
+            column: token.column,
+            span: token.span,
+        }
+    }
+}
+
+// parse a whole script, as scanned by rlox_lib::scan
+pub fn parse_program(tokens: &[Token]) -> Result<Vec<Stmt>, Vec<ParseError>> {
+    Parser::new(&parser_tokens(tokens)).parse_program()
+}
+
+// parse `tokens` as one expression, as scanned by rlox_lib::scan
+pub fn parse_expression(tokens: &[Token]) -> Result<Expr, Vec<ParseError>> {
+    Parser::new(&parser_tokens(tokens)).parse_expression()
+}
+
+// the tokens up to the first Eof without trivia, ending in that Eof; one is
+// made up right after the last token when there is none, so a lossless scan,
+// a slice of one or no tokens at all can be parsed too
+fn parser_tokens(tokens: &[Token]) -> Cow<'_, [Token]> {
+    let is_trivia = |token: &Token| {
+        matches!(
+            token.token_type,
+            TokenType::Comment | TokenType::DocComment | TokenType::Whitespace | TokenType::Newline
+        )
+    };
+    let eof = tokens
+        .iter()
+        .position(|token| token.token_type == TokenType::Eof);
+    if eof.is_some_and(|eof| eof + 1 == tokens.len()) && !tokens.iter().any(is_trivia) {
+        return Cow::Borrowed(tokens);
+    }
+    let end = eof.unwrap_or(tokens.len());
+    let mut kept: Vec<Token> = tokens[..end]
+        .iter()
+        .filter(|token| !is_trivia(token))
+        .cloned()
+        .collect();
+    kept.push(match (eof, tokens.last()) {
+        (Some(eof), _) => tokens[eof].clone(),
+        (None, Some(last)) => {
+            let (line, column) = last.end_position();
+            Token {
+                token_type: TokenType::Eof,
+                lexeme: String::new(),
+                literal: Literal::None,
+                line,
+                column,
+                span: Span {
+                    start: last.span.end,
+                    ..last.span
+                },
+            }
+        }
+        (None, None) => Token {
+            token_type: TokenType::Eof,
+            lexeme: String::new(),
+            literal: Literal::None,
+            line: 1,
+            column: 1,
+            span: Span::default(),
+        },
+    });
+    Cow::Owned(kept)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ScannerConfig;
+    use crate::scan;
+    use crate::scanner::Scanner;
+    use crate::span::Span;
+
+    fn parse(source: &str) -> Result<Expr, Vec<ParseError>> {
+        parse_expression(&scan(source).unwrap())
+    }
+
+    fn printed(source: &str) -> String {
+        parse(source).unwrap().to_string()
+    }
+
+    #[test]
+    fn precedence() {
+        assert_eq!(printed("1 + 2 * 3 - 4"), "(- (+ 1.0 (* 2.0 3.0)) 4.0)");
+        assert_eq!(printed("(1 + 2) * 3"), "(* (group (+ 1.0 2.0)) 3.0)");
+        assert_eq!(printed("-a / !b"), "(/ (- a) (! b))");
+        assert_eq!(printed("!!true == false"), "(== (! (! true)) false)");
+        assert_eq!(printed("1 < 2 == 3 >= 4"), "(== (< 1.0 2.0) (>= 3.0 4.0))");
+        assert_eq!(printed("a or b and c or d"), "(or (or a (and b c)) d)");
+        assert_eq!(printed("a = b = c or d"), "(= a (= b (or c d)))");
+        assert_eq!(printed("nil != \"x\""), "(!= nil \"x\")");
+    }
+
+    #[test]
+    fn calls_and_properties() {
+        assert_eq!(printed("f()(1, g(2))"), "(call (call f) 1.0 (call g 2.0))");
+        assert_eq!(printed("a.b.c(1).d"), "(. (call (. (. a b) c) 1.0) d)");
+        assert_eq!(printed("this.x = super.y"), "(= (. this x) (super y))");
+        assert_eq!(printed("a.b = c = 1"), "(= (. a b) (= c 1.0))");
+    }
+
+    #[test]
+    fn interpolation() {
+        assert_eq!(printed("\"a ${b} c\""), "(+ (+ \"a \" b) \" c\")");
+        assert_eq!(
+            printed("\"${x}${\"${y}\"}\""),
+            "(+ (+ (+ (+ \"\" x) \"\") (+ (+ \"\" y) \"\")) \"\")"
+        );
+    }
+
+    #[test]
+    fn integers() {
+        let config = ScannerConfig::new().integer_literals(true);
+        let tokens = Scanner::with_config("1 + 2.5", config).scan_tokens();
+        assert_eq!(parse_expression(&tokens).unwrap().to_string(), "(+ 1 2.5)");
+    }
+
+    #[test]
+    fn spans() {
+        let source = "foo.bar(1, 2) + -x";
+        let expr = parse(source).unwrap();
+        assert_eq!(expr.span(), Span::new(0, source.len()));
+        let Expr::Binary { left, right, .. } = expr else {
+            panic!("expected a binary expression");
+        };
+        assert_eq!(&source[left.span().range()], "foo.bar(1, 2)");
+        assert_eq!(&source[right.span().range()], "-x");
+        let Expr::Call { callee, .. } = *left else {
+            panic!("expected a call");
+        };
+        assert_eq!(&source[callee.span().range()], "foo.bar");
+    }
+
+    fn program(source: &str) -> Vec<String> {
+        parse_program(&scan(source).unwrap())
+            .unwrap()
+            .iter()
+            .map(Stmt::to_string)
+            .collect()
+    }
+
+    #[test]
+    fn statements() {
+        assert_eq!(
+            program("var a = 1; var b; print a + b; a = 2; { var c = a; print c; }"),
+            vec![
+                "(var a 1.0)",
+                "(var b)",
+                "(print (+ a b))",
+                "(; (= a 2.0))",
+                "(block (var c a) (print c))",
+            ]
+        );
+        assert_eq!(
+            program("if (a) if (b) print 1; else print 2; while (x < 3) x = x + 1;"),
+            vec![
+                "(if a (if b (print 1.0) (print 2.0)))",
+                "(while (< x 3.0) (; (= x (+ x 1.0))))",
+            ]
+        );
+    }
+
+    #[test]
+    fn for_loops() {
+        assert_eq!(
+            program("for (var i = 0; i < 3; i = i + 1) print i;"),
+            vec!["(block (var i 0.0) (while (< i 3.0) (block (print i) (; (= i (+ i 1.0))))))"]
+        );
+        assert_eq!(program("for (;;) f();"), vec!["(while true (; (call f)))"]);
+        assert_eq!(
+            program("for (i = 0; ; ) {}"),
+            vec!["(block (; (= i 0.0)) (while true (block)))"]
+        );
+    }
+
+    #[test]
+    fn functions_and_classes() {
+        assert_eq!(
+            program("fun add(a, b) { return a + b; } fun f() { return; }"),
+            vec!["(fun add (a b) (return (+ a b)))", "(fun f () (return))"]
+        );
+        let source = "class B < A { init(x) { this.x = x; } get() { return super.get(); } }";
+        assert_eq!(
+            program(source),
+            vec![
+                "(class B < A (fun init (x) (; (= (. this x) x))) (fun get () (return (call (super get)))))"
+            ]
+        );
+        assert_eq!(program("class A {}"), vec!["(class A)"]);
+
+        let statements = parse_program(&scan(source).unwrap()).unwrap();
+        let Stmt::Class { methods, span, .. } = &statements[0] else {
+            panic!("expected a class");
+        };
+        assert_eq!(*span, Span::new(0, source.len()));
+        assert_eq!(&source[methods[0].span.range()], "init(x) { this.x = x; }");
+    }
+
+    #[test]
+    fn statement_spans() {
+        let source = "print 1;\nif (a) {\n  b;\n} else c = 2;\nfor (;;) x;";
+        let statements = parse_program(&scan(source).unwrap()).unwrap();
+        let texts: Vec<&str> = statements
+            .iter()
+            .map(|statement| &source[statement.span().range()])
+            .collect();
+        assert_eq!(
+            texts,
+            vec!["print 1;", "if (a) {\n  b;\n} else c = 2;", "for (;;) x;"]
+        );
+    }
+
+    #[test]
+    fn program_errors() {
+        let errors = parse_program(&scan("var 1 = 2;").unwrap()).unwrap_err();
+        assert_eq!(errors[0].message(), "Expect variable name.");
+        let errors = parse_program(&scan("class A { fun f() {} }").unwrap()).unwrap_err();
+        assert_eq!(errors[0].message(), "Expect method name.");
+        assert_eq!(errors[0].found, TokenType::Fun);
+        let errors = parse_program(&scan("{ print 1;").unwrap()).unwrap_err();
+        assert_eq!(errors[0].message(), "Expect '}' after block.");
+        assert_eq!(errors[0].found, TokenType::Eof);
+
+        let params = (0..256)
+            .map(|i| format!("p{}", i))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let errors = parse_program(&scan(&format!("fun f({}) {{}}", params)).unwrap()).unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].kind, ParseErrorKind::TooManyParameters);
+    }
+
+    #[test]
+    fn any_token_slice() {
+        assert_eq!(parse_program(&[]), Ok(vec![]));
+        let errors = parse_expression(&[]).unwrap_err();
+        assert_eq!(errors[0].kind, ParseErrorKind::ExpectedExpression);
+
+        let tokens = scan("print 1;\nprint 2").unwrap();
+        let without_eof = &tokens[..tokens.len() - 1];
+        let errors = parse_program(without_eof).unwrap_err();
+        assert_eq!(errors[0].message(), "Expect ';' after value.");
+        assert_eq!(
+            errors[0].to_string(),
+            "2:8: at end: Expect ';' after value."
+        );
+        assert_eq!(errors[0].span, tokens.last().unwrap().span);
+        assert_eq!(parse_program(&without_eof[..3]).unwrap().len(), 1);
+
+        let source = "// note\nprint 1; /* two */ print 2;\r\n";
+        let config = ScannerConfig::new().lossless(true).emit_comments(true);
+        let tokens = Scanner::with_config(source, config).scan_tokens();
+        assert_eq!(
+            parse_program(&tokens),
+            parse_program(&scan(source).unwrap())
+        );
+    }
+
+    #[test]
+    fn deep_nesting() {
+        let nested = |open: &str, inner: &str, close: &str, depth: usize| {
+            format!("{}{}{}", open.repeat(depth), inner, close.repeat(depth))
+        };
+        // one level below the limit still parses on the default stack of a
+        // test thread, the outermost expression is a level of its own
+        let depth = MAX_NESTING - 1;
+        assert!(parse_expression(&scan(&nested("(", "1", ")", depth)).unwrap()).is_ok());
+        assert!(parse_expression(&scan(&nested("\"${", "1", "}\"", depth)).unwrap()).is_ok());
+        assert!(parse_expression(&scan(&nested("-", "1", "", depth)).unwrap()).is_ok());
+        for source in [
+            nested("(", "1", ")", 100_000),
+            nested("\"${", "1", "}\"", 100_000),
+            nested("-", "1", "", 100_000),
+            nested("{", "", "}", 100_000),
+            nested("if (a) ", "b;", "", 100_000),
+            nested("a = ", "1;", "", 100_000),
+            nested("fun f() {", "", "}", 100_000),
+            nested("class A { f() {", "", "}}", 100_000),
+        ] {
+            let errors = parse_program(&scan(&source).unwrap()).unwrap_err();
+            assert_eq!(errors[0].kind, ParseErrorKind::TooMuchNesting);
+            assert_eq!(errors[0].message(), "Too much nesting.");
+        }
+    }
+
+    #[test]
+    fn recovers_after_errors() {
+        let source = "var a = ;\nprint a;\nfun f(1) {}\nprint a b;\nclass C < { }\n\
+                      if (a) { b = ; } else print 2;\nprint \"done\";";
+        let errors = parse_program(&scan(source).unwrap()).unwrap_err();
+        let found: Vec<(usize, String, &str)> = errors
+            .iter()
+            .map(|error| (error.line, error.message(), &source[error.span.range()]))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                (1, "Expect expression.".to_string(), ";"),
+                (3, "Expect parameter name.".to_string(), "1"),
+                (4, "Expect ';' after value.".to_string(), "b"),
+                (5, "Expect superclass name.".to_string(), "{"),
+                (6, "Expect expression.".to_string(), ";"),
+            ]
+        );
+
+        // the errors come with what would have been accepted instead
+        assert_eq!(errors[0].expected.len(), EXPRESSION_START.len());
+        assert!(
+            EXPRESSION_START
+                .iter()
+                .all(|t| errors[0].expected.contains(t))
+        );
+        assert_eq!(
+            errors[1].expected,
+            vec![TokenType::RightParen, TokenType::Identifier]
+        );
+        assert!(errors[2].expected.contains(&TokenType::Semicolon));
+        assert!(errors[2].expected.contains(&TokenType::Plus));
+        assert!(errors[2].expected.contains(&TokenType::LeftParen));
+    }
+
+    #[test]
+    fn expected_at_statement_start() {
+        let errors = parse_program(&scan("print 1; ) print 2;").unwrap()).unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].found, TokenType::RightParen);
+        for token_type in [TokenType::Class, TokenType::While, TokenType::Bang] {
+            assert!(errors[0].expected.contains(&token_type));
+        }
+        assert_eq!(
+            errors[0].expected.len(),
+            DECLARATION_START.len() + STATEMENT_START.len() + EXPRESSION_START.len()
+        );
+
+        // errors at the end of the file cannot make panic mode loop
+        for source in ["fun", "{", "class A {", "for (", "if (a) print"] {
+            let errors = parse_program(&scan(source).unwrap()).unwrap_err();
+            assert_eq!(errors.len(), 1, "{}", source);
+            assert_eq!(errors[0].found, TokenType::Eof);
+        }
+
+        let config = ScannerConfig::new().integer_literals(true);
+        let tokens = Scanner::with_config("var a = ;\nprint 1 +;", config).scan_tokens();
+        let errors = parse_program(&tokens).unwrap_err();
+        assert_eq!(errors.len(), 2);
+        for error in &errors {
+            assert_eq!(error.kind, ParseErrorKind::ExpectedExpression);
+            assert!(error.expected.contains(&TokenType::Integer));
+        }
+    }
+
+    #[test]
+    fn errors() {
+        let errors = parse("(1 + 2").unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(
+            errors[0].kind,
+            ParseErrorKind::Expected {
+                expected: TokenType::RightParen,
+                message: "Expect ')' after expression.",
+            }
+        );
+        assert_eq!(
+            errors[0].to_string(),
+            "1:7: at end: Expect ')' after expression."
+        );
+
+        let errors = parse("1 + ;").unwrap_err();
+        assert_eq!(errors[0].kind, ParseErrorKind::ExpectedExpression);
+        assert_eq!(errors[0].span, Span::new(4, 5));
+        assert_eq!(errors[0].to_string(), "1:5: at ';': Expect expression.");
+
+        let errors = parse("a + b = c").unwrap_err();
+        assert_eq!(errors[0].kind, ParseErrorKind::InvalidAssignmentTarget);
+        assert_eq!(errors[0].span, Span::new(6, 7));
+
+        let errors = parse("a b").unwrap_err();
+        assert_eq!(errors[0].found, TokenType::Identifier);
+
+        let arguments = vec!["x"; 256].join(", ");
+        let errors = parse(&format!("f({})", arguments)).unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].kind, ParseErrorKind::TooManyArguments);
+    }
+}
@@ -0,0 +1,319 @@
+// machine-readable output for CI: a small JSON schema of our own, and SARIF 2.1
+// for tools that annotate pull requests
+use crate::diagnostic::{Diagnostic, Label, Severity};
+use crate::source_map::SourceMap;
+use crate::span::Span;
+use serde::Serialize;
+use serde_json::{Value, json};
+
+// bumped whenever a field changes meaning or goes away; new fields may be
+// added without a bump
+pub const JSON_SCHEMA_VERSION: u32 = 1;
+
+const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+
+#[derive(Serialize)]
+struct JsonReport<'a> {
+    version: u32,
+    diagnostics: Vec<JsonDiagnostic<'a>>,
+}
+
+#[derive(Serialize)]
+struct JsonDiagnostic<'a> {
+    severity: &'static str,
+    code: Option<&'a str>,
+    message: &'a str,
+    // where the primary label points, when there is one
+    location: Option<JsonLocation>,
+    labels: Vec<JsonLabel<'a>>,
+    notes: &'a [String],
+    help: Option<&'a str>,
+}
+
+#[derive(Serialize)]
+struct JsonLabel<'a> {
+    primary: bool,
+    message: &'a str,
+    location: JsonLocation,
+}
+
+#[derive(Serialize)]
+struct JsonLocation {
+    // None when the span's SourceId is not in the SourceMap
+    file: Option<String>,
+    start: usize,
+    end: usize,
+    line: Option<usize>,
+    column: Option<usize>,
+    end_line: Option<usize>,
+    end_column: Option<usize>,
+}
+
+fn severity_name(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Error => "error",
+        Severity::Warning => "warning",
+        Severity::Note => "note",
+    }
+}
+
+fn json_location(span: Span, sources: &SourceMap) -> JsonLocation {
+    let file = sources.get(span.source);
+    let start = file.and_then(|file| file.line_column(span.start));
+    let end = file.and_then(|file| file.line_column(span.end));
+    JsonLocation {
+        file: file.map(|file| file.path().display().to_string()),
+        start: span.start,
+        end: span.end,
+        line: start.map(|(line, _)| line),
+        column: start.map(|(_, column)| column),
+        end_line: end.map(|(line, _)| line),
+        end_column: end.map(|(_, column)| column),
+    }
+}
+
+fn json_diagnostic<'a>(diagnostic: &'a Diagnostic, sources: &SourceMap) -> JsonDiagnostic<'a> {
+    JsonDiagnostic {
+        severity: severity_name(diagnostic.severity),
+        code: diagnostic.code.as_deref(),
+        message: &diagnostic.message,
+        location: diagnostic
+            .primary_span()
+            .map(|span| json_location(span, sources)),
+        labels: diagnostic
+            .labels
+            .iter()
+            .map(|label| JsonLabel {
+                primary: label.primary,
+                message: &label.message,
+                location: json_location(label.span, sources),
+            })
+            .collect(),
+        notes: &diagnostic.notes,
+        help: diagnostic.help.as_deref(),
+    }
+}
+
+// {"version": 1, "diagnostics": [...]}, see JsonDiagnostic for the fields
+pub fn to_json<'d>(
+    diagnostics: impl IntoIterator<Item = &'d Diagnostic>,
+    sources: &SourceMap,
+) -> String {
+    let report = JsonReport {
+        version: JSON_SCHEMA_VERSION,
+        diagnostics: diagnostics
+            .into_iter()
+            .map(|diagnostic| json_diagnostic(diagnostic, sources))
+            .collect(),
+    };
+    serde_json::to_string_pretty(&report).expect("a report always serializes")
+}
+
+// a SARIF physicalLocation, or None when the span's file is unknown
+fn sarif_location(span: Span, sources: &SourceMap) -> Option<Value> {
+    let file = sources.get(span.source)?;
+    let (start_line, start_column) = file.line_column(span.start)?;
+    let (end_line, end_column) = file.line_column(span.end)?;
+    Some(json!({
+        "physicalLocation": {
+            "artifactLocation": {
+                "uri": file.path().to_string_lossy().replace('\\', "/"),
+            },
+            "region": {
+                "startLine": start_line,
+                "startColumn": start_column,
+                "endLine": end_line,
+                "endColumn": end_column,
+                "byteOffset": span.start,
+                "byteLength": span.len(),
+            },
+        },
+    }))
+}
+
+fn sarif_result(diagnostic: &Diagnostic, sources: &SourceMap) -> Value {
+    let mut text = diagnostic.message.clone();
+    for note in &diagnostic.notes {
+        text.push_str(&format!("\nnote: {}", note));
+    }
+    if let Some(help) = &diagnostic.help {
+        text.push_str(&format!("\nhelp: {}", help));
+    }
+    let (primary, secondary): (Vec<&Label>, Vec<&Label>) =
+        diagnostic.labels.iter().partition(|label| label.primary);
+    let locations: Vec<Value> = diagnostic
+        .primary_span()
+        .and_then(|span| sarif_location(span, sources))
+        .into_iter()
+        .collect();
+    let related: Vec<Value> = primary
+        .iter()
+        .skip(1)
+        .chain(&secondary)
+        .filter_map(|label| {
+            let mut location = sarif_location(label.span, sources)?;
+            if !label.message.is_empty() {
+                location["message"] = json!({ "text": label.message });
+            }
+            Some(location)
+        })
+        .collect();
+
+    let mut result = json!({
+        "level": severity_name(diagnostic.severity),
+        "message": { "text": text },
+        "locations": locations,
+    });
+    if let Some(code) = &diagnostic.code {
+        result["ruleId"] = json!(code);
+    }
+    if !related.is_empty() {
+        result["relatedLocations"] = json!(related);
+    }
+    result
+}
+
+// a SARIF 2.1.0 log with a single run; columns count code points, like
+// everywhere else in the crate
+pub fn to_sarif<'d>(
+    diagnostics: impl IntoIterator<Item = &'d Diagnostic>,
+    sources: &SourceMap,
+) -> String {
+    let diagnostics: Vec<&Diagnostic> = diagnostics.into_iter().collect();
+    let mut rules: Vec<&str> = diagnostics
+        .iter()
+        .filter_map(|diagnostic| diagnostic.code.as_deref())
+        .collect();
+    rules.sort_unstable();
+    rules.dedup();
+    let log = json!({
+        "$schema": SARIF_SCHEMA,
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": env!("CARGO_PKG_NAME"),
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "rules": rules.iter().map(|id| json!({ "id": id })).collect::<Vec<_>>(),
+                },
+            },
+            "columnKind": "unicodeCodePoints",
+            "results": diagnostics
+                .iter()
+                .map(|diagnostic| sarif_result(diagnostic, sources))
+                .collect::<Vec<_>>(),
+        }],
+    });
+    serde_json::to_string_pretty(&log).expect("a SARIF log always serializes")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scanner::Scanner;
+
+    fn scan_diagnostics(sources: &mut SourceMap) -> Vec<Diagnostic> {
+        let id = sources.add("src/main.lox", "var a = 1;\nprint a @ 2;\n");
+        let mut scanner = Scanner::new(sources.get(id).unwrap().text()).source_id(id);
+        scanner.scan_tokens();
+        scanner.errors().iter().map(Diagnostic::from).collect()
+    }
+
+    #[test]
+    fn json_report() {
+        let mut sources = SourceMap::new();
+        let mut diagnostics = scan_diagnostics(&mut sources);
+        diagnostics.push(Diagnostic::warning("Nowhere in particular.").code("W0001"));
+        let report: Value = serde_json::from_str(&to_json(&diagnostics, &sources)).unwrap();
+        assert_eq!(
+            report,
+            json!({
+                "version": 1,
+                "diagnostics": [
+                    {
+                        "severity": "error",
+                        "code": "L0001",
+                        "message": "Unexpected character '@'.",
+                        "location": {
+                            "file": "src/main.lox",
+                            "start": 19,
+                            "end": 20,
+                            "line": 2,
+                            "column": 9,
+                            "end_line": 2,
+                            "end_column": 10,
+                        },
+                        "labels": [{
+                            "primary": true,
+                            "message": "not valid in Lox source",
+                            "location": {
+                                "file": "src/main.lox",
+                                "start": 19,
+                                "end": 20,
+                                "line": 2,
+                                "column": 9,
+                                "end_line": 2,
+                                "end_column": 10,
+                            },
+                        }],
+                        "notes": [],
+                        "help": null,
+                    },
+                    {
+                        "severity": "warning",
+                        "code": "W0001",
+                        "message": "Nowhere in particular.",
+                        "location": null,
+                        "labels": [],
+                        "notes": [],
+                        "help": null,
+                    },
+                ],
+            })
+        );
+    }
+
+    #[test]
+    fn sarif_log() {
+        let mut sources = SourceMap::new();
+        let diagnostics = scan_diagnostics(&mut sources);
+        let with_code = diagnostics[0]
+            .clone()
+            .code("E0001")
+            .label(Label::secondary(Span::new(4, 5), "declared here"))
+            .help("remove it");
+        let log: Value = serde_json::from_str(&to_sarif([&with_code], &sources)).unwrap();
+        assert_eq!(log["version"], "2.1.0");
+        let run = &log["runs"][0];
+        assert_eq!(run["tool"]["driver"]["name"], "rlox-lib");
+        assert_eq!(run["tool"]["driver"]["rules"], json!([{ "id": "E0001" }]));
+        let result = &run["results"][0];
+        assert_eq!(result["ruleId"], "E0001");
+        assert_eq!(result["level"], "error");
+        assert_eq!(
+            result["message"]["text"],
+            "Unexpected character '@'.\nhelp: remove it"
+        );
+        assert_eq!(
+            result["locations"][0]["physicalLocation"],
+            json!({
+                "artifactLocation": { "uri": "src/main.lox" },
+                "region": {
+                    "startLine": 2,
+                    "startColumn": 9,
+                    "endLine": 2,
+                    "endColumn": 10,
+                    "byteOffset": 19,
+                    "byteLength": 1,
+                },
+            })
+        );
+        assert_eq!(
+            result["relatedLocations"][0]["message"]["text"],
+            "declared here"
+        );
+
+        let log: Value = serde_json::from_str(&to_sarif(&diagnostics, &sources)).unwrap();
+        assert_eq!(log["runs"][0]["results"][0]["ruleId"], "L0001");
+    }
+}
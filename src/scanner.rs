@@ -1,121 +1,284 @@
-use crate::token::{Literal, Token, TokenType};
-use std::collections::HashMap;
+use crate::checkpoint::{Checkpoint, Interpolation, LexMode};
+use crate::config::ScannerConfig;
+use crate::error::{ScanError, ScanErrorKind};
+use crate::escape::decode_escape;
+use crate::number::{is_integer_lexeme, parse_integer, parse_number};
+use crate::span::{SourceId, Span};
+use crate::token::{BorrowedToken, Token, TokenType};
+use std::iter::FusedIterator;
+
+// what a single call to scan_token found
+type ScanResult<'src> = Result<BorrowedToken<'src>, ScanError>;
+
 #[derive(Debug)]
-pub struct Scanner {
-    source: String,
-    tokens: Vec<Token>,
+pub struct Scanner<'src> {
+    source: &'src str,
     start: usize,
     current: usize,
     line: usize,
-    has_error: bool,
-    keywords: HashMap<String, TokenType>,
+    // 1-based column, counted in chars, of the char at current
+    column: usize,
+    start_line: usize,
+    start_column: usize,
+    reached_eof: bool,
+    // what the next call to scan_token has to pick up
+    mode: LexMode,
+    interpolations: Vec<Interpolation>,
+    errors: Vec<ScanError>,
+    config: ScannerConfig,
+    source_id: SourceId,
 }
 
-impl Scanner {
-    pub fn new(source: String) -> Self {
-        let mut keywords: HashMap<String, TokenType> = HashMap::new();
-        keywords.insert(String::from("and"), TokenType::And);
-        keywords.insert(String::from("class"), TokenType::Class);
-        keywords.insert(String::from("else"), TokenType::Else);
-        keywords.insert(String::from("false"), TokenType::False);
-        keywords.insert(String::from("for"), TokenType::For);
-        keywords.insert(String::from("fun"), TokenType::Fun);
-        keywords.insert(String::from("if"), TokenType::If);
-        keywords.insert(String::from("nil"), TokenType::Nil);
-        keywords.insert(String::from("or"), TokenType::Or);
-        keywords.insert(String::from("print"), TokenType::Print);
-        keywords.insert(String::from("return"), TokenType::Return);
-        keywords.insert(String::from("super"), TokenType::Super);
-        keywords.insert(String::from("this"), TokenType::This);
-        keywords.insert(String::from("true"), TokenType::True);
-        keywords.insert(String::from("var"), TokenType::Var);
-        keywords.insert(String::from("while"), TokenType::While);
+impl<'src> Scanner<'src> {
+    pub fn new(source: &'src str) -> Self {
+        Self::with_config(source, ScannerConfig::default())
+    }
+
+    pub fn with_config(source: &'src str, config: ScannerConfig) -> Self {
         Scanner {
-            source: source,
-            tokens: vec![],
+            source,
             start: 0,
             current: 0,
             line: 1,
-            has_error: false,
-            keywords: keywords,
+            column: 1,
+            start_line: 1,
+            start_column: 1,
+            reached_eof: false,
+            mode: LexMode::Code,
+            interpolations: vec![],
+            errors: vec![],
+            config,
+            source_id: SourceId::default(),
+        }
+    }
+
+    // start scanning part way through `source`, e.g. from Checkpoint::at
+    pub fn from_checkpoint(
+        source: &'src str,
+        config: ScannerConfig,
+        checkpoint: &Checkpoint,
+    ) -> Self {
+        let mut scanner = Self::with_config(source, config);
+        scanner.restore(checkpoint);
+        scanner
+    }
+
+    // which file of a SourceMap the source is, stamped on every span
+    pub fn source_id(mut self, source_id: SourceId) -> Self {
+        self.source_id = source_id;
+        self
+    }
+
+    pub fn checkpoint(&self) -> Checkpoint {
+        Checkpoint {
+            offset: self.current,
+            line: self.line,
+            column: self.column,
+            mode: self.mode,
+            interpolations: self.interpolations.clone(),
+            reached_eof: self.reached_eof,
+            error_count: self.errors.len(),
         }
     }
 
-    pub fn show_self(&self) -> (&String, &Vec<Token>, &usize, &usize, &usize, &bool) {
-        return (
-            &self.source,
-            &self.tokens,
+    // go back to a checkpoint of this scanner, forgetting the errors found
+    // since; a checkpoint that does not fit the source, past its end, inside
+    // a char or at an InterpolationStart without "${", goes on as plain code
+    // from the nearest char boundary before its offset
+    pub fn restore(&mut self, checkpoint: &Checkpoint) {
+        let offset = self
+            .source
+            .floor_char_boundary(checkpoint.offset.min(self.source.len()));
+        let fits = offset == checkpoint.offset
+            && (checkpoint.mode != LexMode::InterpolationStart
+                || self.source[offset..].starts_with("${"));
+        self.start = offset;
+        self.current = offset;
+        self.line = checkpoint.line;
+        self.column = checkpoint.column;
+        self.start_line = checkpoint.line;
+        self.start_column = checkpoint.column;
+        (self.mode, self.interpolations) = match fits {
+            true => (checkpoint.mode, checkpoint.interpolations.clone()),
+            false => (LexMode::Code, vec![]),
+        };
+        self.reached_eof = checkpoint.reached_eof;
+        self.errors.truncate(checkpoint.error_count);
+    }
+
+    pub fn mode(&self) -> LexMode {
+        self.mode
+    }
+
+    // true when the next token starts in plain code, outside any interpolation
+    pub fn at_token_boundary(&self) -> bool {
+        self.mode == LexMode::Code && self.interpolations.is_empty()
+    }
+
+    pub fn show_self(&self) -> (&str, &usize, &usize, &usize, &[ScanError]) {
+        (
+            self.source,
             &self.start,
             &self.current,
             &self.line,
-            &self.has_error,
-        );
+            &self.errors,
+        )
     }
 
-    // 遍历文章全部token
-    fn scan_tokens(&mut self) -> &Vec<Token> {
-        while !self.current_at_end() {
-            self.scan_token();
+    // every error found so far, however the tokens were asked for
+    pub fn errors(&self) -> &[ScanError] {
+        &self.errors
+    }
+
+    // 遍历文章全部token, collecting owned copies
+    pub fn scan_tokens(&mut self) -> Vec<Token> {
+        self.scan_borrowed().iter().map(Token::from).collect()
+    }
+
+    // 遍历文章全部token without copying any lexeme out of the source
+    pub fn scan_borrowed(&mut self) -> Vec<BorrowedToken<'src>> {
+        let mut tokens = vec![];
+        while let Some(token) = self.next_token() {
+            tokens.push(token);
+        }
+        tokens
+    }
+
+    // the next token, where an error becomes a TokenType::Error token covering
+    // the text skipped over, so the stream always moves on past bad input
+    pub fn next_token(&mut self) -> Option<BorrowedToken<'src>> {
+        let token = match self.next_result()? {
+            Ok(token) => token,
+            Err(error) => {
+                let token = self.add_token(TokenType::Error(error.kind.clone()));
+                self.errors.push(error);
+                token
+            }
+        };
+        Some(token)
+    }
+
+    fn next_result(&mut self) -> Option<ScanResult<'src>> {
+        while !self.current_at_end() || self.mode != LexMode::Code {
+            if let Some(result) = self.scan_token() {
+                return Some(result);
+            }
+        }
+        if let Some(error) = self.unclosed_interpolation() {
+            return Some(Err(error));
+        }
+        if self.reached_eof {
+            return None;
         }
-        &self.tokens
+        Some(Ok(self.add_eof()))
     }
 
-    // capture the single token
-    fn scan_token(&mut self) {
+    // capture the single token, None when only whitespace was consumed
+    fn scan_token(&mut self) -> Option<ScanResult<'src>> {
         let text_char = self.current_char();
         self.start = self.current;
+        self.start_line = self.line;
+        self.start_column = self.column;
+        match self.mode {
+            LexMode::Code => {}
+            LexMode::InterpolationStart => return Some(Ok(self.add_interpolation_start())),
+            LexMode::String => {
+                self.mode = LexMode::Code;
+                return Some(self.add_string(false));
+            }
+            LexMode::BlockComment { depth } => {
+                self.mode = LexMode::Code;
+                return self.add_block_comment(depth);
+            }
+        }
         self.consume_char();
 
-        match text_char {
+        let token_type = match text_char {
             // match a single character
-            '(' => self.add_token(TokenType::LeftParen, Literal::None),
-            ')' => self.add_token(TokenType::RightParen, Literal::None),
-            '{' => self.add_token(TokenType::LeftBrace, Literal::None),
-            '}' => self.add_token(TokenType::RightBrace, Literal::None),
-            ',' => self.add_token(TokenType::Comma, Literal::None),
-            '.' => self.add_token(TokenType::Dot, Literal::None),
-            '-' => self.add_token(TokenType::Minus, Literal::None),
-            '+' => self.add_token(TokenType::Semicolon, Literal::None),
-            ';' => self.add_token(TokenType::Semicolon, Literal::None),
-            '*' => self.add_token(TokenType::Star, Literal::None),
+            '(' => TokenType::LeftParen,
+            ')' => TokenType::RightParen,
+            '{' => {
+                if let Some(interpolation) = self.interpolations.last_mut() {
+                    interpolation.depth += 1;
+                }
+                TokenType::LeftBrace
+            }
+            '}' => match self.interpolations.last_mut() {
+                Some(interpolation) if interpolation.depth == 0 => {
+                    self.interpolations.pop();
+                    self.mode = LexMode::String;
+                    TokenType::InterpolationEnd
+                }
+                Some(interpolation) => {
+                    interpolation.depth -= 1;
+                    TokenType::RightBrace
+                }
+                None => TokenType::RightBrace,
+            },
+            ',' => TokenType::Comma,
+            '.' => TokenType::Dot,
+            '-' => TokenType::Minus,
+            '+' => TokenType::Plus,
+            ';' => TokenType::Semicolon,
+            '*' => TokenType::Star,
 
             // conditional comsume two charafcter
             '!' => match self.peek_if_two_char_symbol('=') {
-                true => self.add_token(TokenType::BangEqual, Literal::None),
-                false => self.add_token(TokenType::Bang, Literal::None),
+                true => TokenType::BangEqual,
+                false => TokenType::Bang,
             },
             '=' => match self.peek_if_two_char_symbol('=') {
-                true => self.add_token(TokenType::EqualEqual, Literal::None),
-                false => self.add_token(TokenType::Equal, Literal::None),
+                true => TokenType::EqualEqual,
+                false => TokenType::Equal,
             },
             '>' => match self.peek_if_two_char_symbol('=') {
-                true => self.add_token(TokenType::GreaterEqual, Literal::None),
-                false => self.add_token(TokenType::Greater, Literal::None),
+                true => TokenType::GreaterEqual,
+                false => TokenType::Greater,
             },
             '<' => match self.peek_if_two_char_symbol('=') {
-                true => self.add_token(TokenType::LessEqual, Literal::None),
-                false => self.add_token(TokenType::LessEqual, Literal::None),
+                true => TokenType::LessEqual,
+                false => TokenType::Less,
             },
 
-            '/' => self.add_token(TokenType::Slash, Literal::None),
+            '/' => match self.current_char() {
+                '/' => return self.add_line_comment(),
+                '*' => {
+                    self.consume_char();
+                    return self.add_block_comment(1);
+                }
+                _ => TokenType::Slash,
+            },
 
-            // newline and whitespace
-            ' ' => {}
-            '\r' => {}
-            '\t' => {}
-            '\n' => self.line += 1,
+            // newline and whitespace, "\r\n" counts as a single newline
+            '\r' if self.current_char() == '\n' => {
+                self.consume_char();
+                self.new_line();
+                return self.add_trivia(TokenType::Newline);
+            }
+            '\n' => {
+                self.new_line();
+                return self.add_trivia(TokenType::Newline);
+            }
+            ' ' | '\r' | '\t' => {
+                while matches!(self.current_char(), ' ' | '\t')
+                    || (self.current_char() == '\r' && self.next_char() != '\n')
+                {
+                    self.consume_char();
+                }
+                return self.add_trivia(TokenType::Whitespace);
+            }
 
             // string literals
-            '"' => self.add_string(),
-            _ => match self.current_char() {
-                c if c.is_ascii_digit() => self.add_number(),
-                c if c.is_ascii_alphabetic() || c=='_' =>self.add_identifier(),
-                _ => {
-                    eprintln!("{}: Unexpected character.", self.line);
-                    self.has_error = true;
-                }
-            },
-        }
+            '"' => return Some(self.add_string(true)),
+            c if c.is_ascii_digit() => return Some(self.add_number()),
+            c if self.is_identifier_start(c) => return Some(self.add_identifier()),
+            c => return Some(Err(self.error(ScanErrorKind::UnexpectedCharacter(c)))),
+        };
+        Some(Ok(self.add_token(token_type)))
+    }
+
+    fn span(&self, start: usize, end: usize) -> Span {
+        Span::new(start, end).in_source(self.source_id)
     }
 
     fn peek_if_two_char_symbol(&mut self, expected: char) -> bool {
@@ -129,198 +292,1008 @@ impl Scanner {
         }
         match_res
     }
-    fn add_token(&mut self, tokentype: TokenType, literal: Literal) {
-        let text = &self.source[self.start..self.current];
-        self.tokens.push(Token {
+    fn add_token(&self, tokentype: TokenType) -> BorrowedToken<'src> {
+        BorrowedToken {
             token_type: tokentype,
-            lexeme: text.to_string(),
-            literal: literal,
+            lexeme: &self.source[self.start..self.current],
+            line: self.start_line,
+            column: self.start_column,
+            span: self.span(self.start, self.current),
+        }
+    }
+    fn add_eof(&mut self) -> BorrowedToken<'src> {
+        self.reached_eof = true;
+        BorrowedToken {
+            token_type: TokenType::Eof,
+            lexeme: "",
             line: self.line,
-        })
+            column: self.column,
+            span: self.span(self.current, self.current),
+        }
     }
-    fn add_identifier(&mut self) {
-        while self.current_char().is_alphanumeric() || self.current_char() == '_' {
-            self.current += 1;
+    fn error(&self, kind: ScanErrorKind) -> ScanError {
+        ScanError {
+            kind,
+            line: self.start_line,
+            column: self.start_column,
+            span: self.span(self.start, self.current),
         }
-        let text = self.source.get(self.start..self.current).unwrap();
-        match self.keywords.get(text) {
-            Some(token_type) => self.add_token(token_type.clone(), Literal::None),
-            None => self.add_token(TokenType::Identifier, Literal::None),
+    }
+    fn add_line_comment(&mut self) -> Option<ScanResult<'src>> {
+        // the comment runs up to, but not including, the newline or "\r\n"
+        while !matches!(
+            (self.current_char(), self.next_char()),
+            ('\n', _) | ('\r', '\n')
+        ) && !self.current_at_end()
+        {
+            self.consume_char();
         }
+        let text = &self.source[self.start..self.current];
+        // "///" starts a doc comment, "////" and longer are plain again
+        let is_doc = text.starts_with("///") && !text.starts_with("////");
+        self.add_comment(is_doc)
     }
-    fn add_number(&mut self) {
-        self.start = self.current;
-        while self.current_char().is_ascii_digit() {
+    fn add_block_comment(&mut self, mut depth: usize) -> Option<ScanResult<'src>> {
+        // now the current is pointing the char after "/*", or wherever a
+        // checkpoint inside the comment left off
+        while depth > 0 {
+            if self.current_at_end() {
+                return Some(Err(self.error(ScanErrorKind::UnterminatedComment)));
+            }
+            match (self.current_char(), self.next_char()) {
+                ('/', '*') => {
+                    self.consume_char();
+                    self.consume_char();
+                    depth += 1;
+                }
+                ('*', '/') => {
+                    self.consume_char();
+                    self.consume_char();
+                    depth -= 1;
+                }
+                ('\n', _) => {
+                    self.consume_char();
+                    self.new_line();
+                }
+                _ => self.consume_char(),
+            }
+        }
+        let text = &self.source[self.start..self.current];
+        // "/**" starts a doc comment, but "/**/" is empty and "/***" is decoration
+        let is_doc = text.starts_with("/**") && !text.starts_with("/***") && text != "/**/";
+        self.add_comment(is_doc)
+    }
+    fn add_comment(&self, is_doc: bool) -> Option<ScanResult<'src>> {
+        if !self.config.emit_comments && !self.config.lossless {
+            return None;
+        }
+        let token_type = match is_doc {
+            true => TokenType::DocComment,
+            false => TokenType::Comment,
+        };
+        Some(Ok(self.add_token(token_type)))
+    }
+    fn add_trivia(&self, token_type: TokenType) -> Option<ScanResult<'src>> {
+        if !self.config.lossless {
+            return None;
+        }
+        Some(Ok(self.add_token(token_type)))
+    }
+    fn add_identifier(&mut self) -> ScanResult<'src> {
+        while self.is_identifier_continue(self.current_char()) {
             self.consume_char();
         }
-        if self.current_char() == '.' && self.next_char().is_ascii_digit() {
+        let text = &self.source[self.start..self.current];
+        match self.config.dialect.keyword(text) {
+            Some(token_type) => Ok(self.add_token(token_type)),
+            None => Ok(self.add_token(TokenType::Identifier)),
+        }
+    }
+    fn add_number(&mut self) -> ScanResult<'src> {
+        // now the current is pointing the char after the first digit
+        let first = &self.source[self.start..self.current];
+        if first == "0" && matches!(self.current_char(), 'x' | 'X' | 'b' | 'B' | 'o' | 'O') {
+            // take every letter and digit so that "0b102" is one malformed literal
             self.consume_char();
+            while self.current_char().is_ascii_alphanumeric() || self.current_char() == '_' {
+                self.consume_char();
+            }
+        } else {
+            self.consume_digits();
+            if self.current_char() == '.' && self.next_char().is_ascii_digit() {
+                self.consume_char();
+                self.consume_digits();
+            }
+            let exponent_digit = match self.next_char() {
+                '+' | '-' => self.nth_char(2),
+                c => c,
+            };
+            if matches!(self.current_char(), 'e' | 'E') && exponent_digit.is_ascii_digit() {
+                self.consume_char();
+                if matches!(self.current_char(), '+' | '-') {
+                    self.consume_char();
+                }
+                self.consume_digits();
+            }
         }
-        while self.current_char().is_ascii_digit() {
+        let text = &self.source[self.start..self.current];
+        let checked = match self.config.integer_literals && is_integer_lexeme(text) {
+            true => parse_integer(text).map(|_| TokenType::Integer),
+            false => parse_number(text).map(|_| TokenType::Number),
+        };
+        match checked {
+            Ok(token_type) => Ok(self.add_token(token_type)),
+            Err(kind) => Err(self.error(kind)),
+        }
+    }
+    fn consume_digits(&mut self) {
+        while self.current_char().is_ascii_digit() || self.current_char() == '_' {
             self.consume_char();
         }
-        let value = (&self.source[self.start..self.current])
-            .parse::<f64>()
-            .unwrap();
-        self.add_token(TokenType::Number, Literal::Number(value));
-    }
-    fn add_string(&mut self) {
-        // now the current is pointing the char "
-        //the start point the first char " in string
-        while self.current_char() != '"' {
-            if self.current_char() == '\n' {
-                self.line += 1;
+    }
+    // `opening` is true when the opening quote has just been consumed, and false
+    // when carrying on after an interpolation; a string containing "${" comes out
+    // as StringPart tokens around the interpolated tokens
+    fn add_string(&mut self, opening: bool) -> ScanResult<'src> {
+        // now the current is pointing the char after " (or after the closing })
+        // only the first bad escape is reported, after the whole part is consumed
+        let mut escape_error = None;
+        let token_type = loop {
+            if self.current_at_end() {
+                return Err(self.error(ScanErrorKind::UnterminatedString));
+            }
+            let c = self.current_char();
+            if c == '$' && self.next_char() == '{' {
+                // leave "${" for the InterpolationStart token
+                self.mode = LexMode::InterpolationStart;
+                break TokenType::StringPart;
             }
             self.consume_char();
+            match c {
+                '"' if opening => break TokenType::String,
+                '"' => break TokenType::StringPart,
+                '\n' => self.new_line(),
+                '\\' => {
+                    let error = self.consume_escape();
+                    escape_error = escape_error.or(error);
+                }
+                _ => {}
+            }
+        };
+        match escape_error {
+            Some(error) => Err(error),
+            None => Ok(self.add_token(token_type)),
         }
-        // now the current is pointing the final char " in string
-        if self.current_at_end() {
-            eprintln!("{}:Unterminated string.", self.line);
-            self.has_error = true;
-        }
-
+    }
+    fn add_interpolation_start(&mut self) -> BorrowedToken<'src> {
+        // now the current is pointing the '$' of "${"
+        self.mode = LexMode::Code;
         self.consume_char();
-        // now the current is pointing the char after "
-        let value = self
-            .source
-            .get(self.start..self.current)
-            .unwrap()
-            .to_string();
-        self.add_token(TokenType::String, Literal::String(value));
+        self.consume_char();
+        self.interpolations.push(Interpolation {
+            depth: 0,
+            start: self.start,
+            line: self.start_line,
+            column: self.start_column,
+        });
+        self.add_token(TokenType::InterpolationStart)
+    }
+    fn unclosed_interpolation(&mut self) -> Option<ScanError> {
+        let interpolation = self.interpolations.pop()?;
+        // the Error token sits at the end of the source, the error points at "${"
+        self.start = self.current;
+        self.start_line = self.line;
+        self.start_column = self.column;
+        Some(ScanError {
+            kind: ScanErrorKind::UnterminatedInterpolation,
+            line: interpolation.line,
+            column: interpolation.column,
+            span: self.span(interpolation.start, interpolation.start + 2),
+        })
+    }
+    fn consume_escape(&mut self) -> Option<ScanError> {
+        // now the current is pointing the char after the backslash
+        let escape_start = self.current - 1;
+        let column = self.column - 1;
+        let (end, error) = match decode_escape(&self.source[self.current..]) {
+            Ok((_, len)) => (self.current + len, None),
+            Err((kind, len)) => (self.current + len, Some(kind)),
+        };
+        // a malformed escape never steps over the closing quote or a newline
+        while self.current < end && (error.is_none() || !matches!(self.current_char(), '"' | '\n'))
+        {
+            self.consume_char();
+        }
+        error.map(|kind| ScanError {
+            kind,
+            line: self.line,
+            column,
+            span: self.span(escape_start, self.current),
+        })
+    }
+    fn is_identifier_start(&self, c: char) -> bool {
+        if self.config.unicode_identifiers {
+            c == '_' || unicode_ident::is_xid_start(c)
+        } else {
+            c.is_ascii_alphabetic() || c == '_'
+        }
+    }
+    fn is_identifier_continue(&self, c: char) -> bool {
+        if self.config.unicode_identifiers {
+            unicode_ident::is_xid_continue(c)
+        } else {
+            c.is_ascii_alphanumeric() || c == '_'
+        }
+    }
+    fn new_line(&mut self) {
+        // called once the '\n' has been consumed
+        self.line += 1;
+        self.column = 1;
     }
     fn consume_char(&mut self) {
-        //cthe current point the next char
-        self.current += 1;
+        //cthe current point the next char, which may be several bytes on
+        self.current += self.current_char().len_utf8();
+        self.column += 1;
     }
 
     fn current_char(&self) -> char {
-        //  return the char pointed to by current
-        self.source.as_bytes()[self.current] as char
+        //  return the char pointed to by current, '\0' once past the end
+        self.source[self.current..].chars().next().unwrap_or('\0')
     }
     fn next_char(&self) -> char {
-        self.source.as_bytes()[self.current + 1] as char
+        self.nth_char(1)
+    }
+    fn nth_char(&self, n: usize) -> char {
+        self.source[self.current..].chars().nth(n).unwrap_or('\0')
     }
     fn current_at_end(&self) -> bool {
         self.current >= self.source.len()
     }
 }
 
+// tokens are produced on demand; the stream ends with exactly one Eof
+impl<'src> Iterator for Scanner<'src> {
+    type Item = ScanResult<'src>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let result = self.next_result()?;
+        if let Err(error) = &result {
+            self.errors.push(error.clone());
+        }
+        Some(result)
+    }
+}
+
+impl FusedIterator for Scanner<'_> {}
+
 #[cfg(test)]
 mod test_scanner {
     use super::*;
+    use crate::checkpoint::{Checkpoint, LexMode};
+    use crate::dialect::Dialect;
+    use crate::token::Literal;
+    use std::sync::Arc;
     use std::vec;
     #[test]
     fn test_new() {
         let source_str = String::from("hello world");
-        let scanner = Scanner::new(source_str);
-        let (source, tokens, start, current, line, has_error) = scanner.show_self();
-        assert_eq!(*source, "hello world".to_string());
-        assert_eq!(*tokens, vec![]);
+        let scanner = Scanner::new(&source_str);
+        let (source, start, current, line, errors) = scanner.show_self();
+        assert_eq!(source, "hello world");
         assert_eq!(*start, 0);
         assert_eq!(*current, 0);
         assert_eq!(*line, 1);
-        assert_eq!(*has_error, false);
+        assert!(errors.is_empty());
     }
     #[test]
     fn test_scan_one_char() {
         let source_str = String::from("(");
-        let mut scanner = Scanner::new(source_str);
-        scanner.scan_tokens();
-        let (_, tokens, start, current, line, has_error) = scanner.show_self();
-        let token_list = vec![Token {
-            token_type: TokenType::LeftParen,
-            lexeme: "(".to_string(),
-            literal: Literal::None,
-            line: 1,
-        }];
-        assert_eq!(*tokens, token_list);
+        let mut scanner = Scanner::new(&source_str);
+        let tokens = scanner.scan_tokens();
+        let (_, start, current, line, errors) = scanner.show_self();
+        let token_list = vec![
+            Token {
+                token_type: TokenType::LeftParen,
+                lexeme: "(".to_string(),
+                literal: Literal::None,
+                line: 1,
+                column: 1,
+                span: Span::new(0, 1),
+            },
+            Token {
+                token_type: TokenType::Eof,
+                lexeme: String::new(),
+                literal: Literal::None,
+                line: 1,
+                column: 2,
+                span: Span::new(1, 1),
+            },
+        ];
+        assert_eq!(tokens, token_list);
         assert_eq!(*start, 0);
         assert_eq!(*current, 1);
         assert_eq!(*line, 1);
-        assert_eq!(*has_error, false);
+        assert!(errors.is_empty());
     }
     #[test]
     fn test_scan_two_char() {
         let source_str = String::from(" >= ");
-        let mut scanner = Scanner::new(source_str);
-        scanner.scan_tokens();
-        let (_, tokens, start, current, line, has_error) = scanner.show_self();
-        let token_list = vec![Token {
-            token_type: TokenType::GreaterEqual,
-            lexeme: ">=".to_string(),
-            literal: Literal::None,
-            line: 1,
-        }];
-        assert_eq!(*tokens, token_list);
+        let mut scanner = Scanner::new(&source_str);
+        let tokens = scanner.scan_tokens();
+        let (_, start, current, line, errors) = scanner.show_self();
+        let token_list = vec![
+            Token {
+                token_type: TokenType::GreaterEqual,
+                lexeme: ">=".to_string(),
+                literal: Literal::None,
+                line: 1,
+                column: 2,
+                span: Span::new(1, 3),
+            },
+            Token {
+                token_type: TokenType::Eof,
+                lexeme: String::new(),
+                literal: Literal::None,
+                line: 1,
+                column: 5,
+                span: Span::new(4, 4),
+            },
+        ];
+        assert_eq!(tokens, token_list);
         assert_eq!(*start, 3);
         assert_eq!(*current, 4);
         assert_eq!(*line, 1);
-        assert_eq!(*has_error, false);
+        assert!(errors.is_empty());
     }
     #[test]
     fn test_add_string() {
         let source_str = String::from(" \"hello\" ");
-        let mut scanner = Scanner::new(source_str);
-        scanner.scan_tokens();
-        let (_, tokens, start, current, line, has_error) = scanner.show_self();
-        let token_list = vec![Token {
-            token_type: TokenType::String,
-            lexeme: "\"hello\"".to_string(),
-            literal: Literal::String("\"hello\"".to_string()),
-            line: 1,
-        }];
-        assert_eq!(*tokens, token_list);
+        let mut scanner = Scanner::new(&source_str);
+        let tokens = scanner.scan_tokens();
+        let (_, start, current, line, errors) = scanner.show_self();
+        let token_list = vec![
+            Token {
+                token_type: TokenType::String,
+                lexeme: "\"hello\"".to_string(),
+                literal: Literal::String("hello".to_string()),
+                line: 1,
+                column: 2,
+                span: Span::new(1, 8),
+            },
+            Token {
+                token_type: TokenType::Eof,
+                lexeme: String::new(),
+                literal: Literal::None,
+                line: 1,
+                column: 10,
+                span: Span::new(9, 9),
+            },
+        ];
+        assert_eq!(tokens, token_list);
         assert_eq!(*start, 8);
         assert_eq!(*current, 9);
         assert_eq!(*line, 1);
-        assert_eq!(*has_error, false);
+        assert!(errors.is_empty());
     }
 
     #[test]
     fn test_add_string2() {
         let source_str = String::from(" \"hello\"");
-        let mut scanner = Scanner::new(source_str);
-        scanner.scan_tokens();
-        let (_, tokens, start, current, line, has_error) = scanner.show_self();
-        let token_list = vec![Token {
-            token_type: TokenType::String,
-            lexeme: "\"hello\"".to_string(),
-            literal: Literal::String("\"hello\"".to_string()),
-            line: 1,
-        }];
-        assert_eq!(*tokens, token_list);
+        let mut scanner = Scanner::new(&source_str);
+        let tokens = scanner.scan_tokens();
+        let (_, start, current, line, errors) = scanner.show_self();
+        let token_list = vec![
+            Token {
+                token_type: TokenType::String,
+                lexeme: "\"hello\"".to_string(),
+                literal: Literal::String("hello".to_string()),
+                line: 1,
+                column: 2,
+                span: Span::new(1, 8),
+            },
+            Token {
+                token_type: TokenType::Eof,
+                lexeme: String::new(),
+                literal: Literal::None,
+                line: 1,
+                column: 9,
+                span: Span::new(8, 8),
+            },
+        ];
+        assert_eq!(tokens, token_list);
         assert_eq!(*start, 1);
         assert_eq!(*current, 8);
         assert_eq!(*line, 1);
-        assert_eq!(*has_error, false);
+        assert!(errors.is_empty());
     }
 
     #[test]
     fn test_add_number() {
         let source_str = String::from("123.456");
-        let mut scanner = Scanner::new(source_str);
+        let mut scanner = Scanner::new(&source_str);
+        let tokens = scanner.scan_tokens();
+        let token_list = vec![
+            Token {
+                token_type: TokenType::Number,
+                lexeme: "123.456".to_string(),
+                literal: Literal::Number(123.456),
+                line: 1,
+                column: 1,
+                span: Span::new(0, 7),
+            },
+            Token {
+                token_type: TokenType::Eof,
+                lexeme: String::new(),
+                literal: Literal::None,
+                line: 1,
+                column: 8,
+                span: Span::new(7, 7),
+            },
+        ];
+        assert_eq!(tokens, token_list);
+    }
+
+    #[test]
+    fn test_multiline_token_position() {
+        let source_str = String::from("x\n  \"a\nb\" y");
+        let mut scanner = Scanner::new(&source_str);
+        let tokens = scanner.scan_tokens();
+        assert_eq!((tokens[1].line, tokens[1].column), (2, 3));
+        assert_eq!(tokens[1].span, Span::new(4, 9));
+        assert_eq!((tokens[2].line, tokens[2].column), (3, 4));
+        assert_eq!((tokens[3].line, tokens[3].column), (3, 5));
+        assert_eq!(tokens[3].span, Span::new(11, 11));
+    }
+
+    #[test]
+    fn test_scan_borrowed() {
+        let source_str = String::from("var s = \"hi\";");
+        let mut scanner = Scanner::new(&source_str);
+        let tokens = scanner.scan_borrowed();
+        assert_eq!(tokens.len(), 6);
+        assert_eq!(tokens[3].lexeme, "\"hi\"");
+        // the lexeme points into the source instead of a fresh allocation
+        assert_eq!(tokens[3].lexeme.as_ptr(), source_str[8..].as_ptr());
+        assert_eq!(tokens[3].literal(), Literal::String("hi".to_string()));
+        assert_eq!(tokens[3].to_token().lexeme, "\"hi\"");
+    }
+
+    #[test]
+    fn test_iterator() {
+        let mut scanner = Scanner::new("a = 1;").peekable();
+        let peeked = scanner.peek().unwrap().as_ref().unwrap();
+        assert_eq!(peeked.token_type, TokenType::Identifier);
+        let types: Vec<TokenType> = scanner.map(|token| token.unwrap().token_type).collect();
+        assert_eq!(
+            types,
+            vec![
+                TokenType::Identifier,
+                TokenType::Equal,
+                TokenType::Number,
+                TokenType::Semicolon,
+                TokenType::Eof,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_iterator_stops_at_first_error() {
+        let mut scanner = Scanner::new("a ~ b ~ c");
+        let result: Result<Vec<_>, _> = scanner.by_ref().collect();
+        assert_eq!(
+            result.unwrap_err().kind,
+            ScanErrorKind::UnexpectedCharacter('~')
+        );
+        // nothing past the error has been scanned yet
+        let (_, _, current, _, _) = scanner.show_self();
+        assert_eq!(*current, 3);
+        assert_eq!(scanner.next().unwrap().unwrap().lexeme, "b");
+    }
+
+    #[test]
+    fn test_unicode_source() {
+        let mut scanner = Scanner::new("\"héllo ✓\" x");
+        let tokens = scanner.scan_tokens();
+        assert_eq!(tokens[0].literal, Literal::String("héllo ✓".to_string()));
+        assert_eq!(tokens[0].span, Span::new(0, 12));
+        assert_eq!(tokens[1].lexeme, "x");
+        assert_eq!(tokens[1].span, Span::new(13, 14));
+        // columns count chars, not bytes
+        assert_eq!(tokens[1].column, 11);
+    }
+
+    #[test]
+    fn test_unicode_identifiers() {
+        let mut scanner = Scanner::new("café");
         scanner.scan_tokens();
-        let (_, tokens, _, _, _, _) = scanner.show_self();
-        let token_list = vec![Token {
-            token_type: TokenType::Number,
-            lexeme: "123.456".to_string(),
-            literal: Literal::Number(123.456),
-            line: 1,
-        }];
-        assert_eq!(*tokens, token_list);
+        assert_eq!(
+            scanner.errors()[0].kind,
+            ScanErrorKind::UnexpectedCharacter('é')
+        );
+        assert_eq!(scanner.errors()[0].span, Span::new(3, 5));
+
+        let config = ScannerConfig::new().unicode_identifiers(true);
+        let mut scanner = Scanner::with_config("café 名前_1 _ü 1x", config);
+        let tokens = scanner.scan_tokens();
+        let lexemes: Vec<&str> = tokens.iter().map(|t| t.lexeme.as_str()).collect();
+        assert_eq!(lexemes, vec!["café", "名前_1", "_ü", "1", "x", ""]);
+        assert_eq!(tokens[1].token_type, TokenType::Identifier);
+        assert!(scanner.errors().is_empty());
     }
-}
 
+    #[test]
+    fn test_comments_are_skipped() {
+        let mut scanner = Scanner::new("a // b\n/* c /* d */ e\n*/ f / g");
+        let tokens = scanner.scan_tokens();
+        let lexemes: Vec<&str> = tokens.iter().map(|t| t.lexeme.as_str()).collect();
+        assert_eq!(lexemes, vec!["a", "f", "/", "g", ""]);
+        assert_eq!((tokens[1].line, tokens[1].column), (3, 4));
+        assert!(scanner.errors().is_empty());
+    }
+
+    #[test]
+    fn test_emit_comments() {
+        let config = ScannerConfig::new().emit_comments(true);
+        let source = "/// doc\n// plain\n//// rule\n/** block doc */ /**/ /* a /* b */ */ x";
+        let mut scanner = Scanner::with_config(source, config);
+        let tokens = scanner.scan_tokens();
+        let found: Vec<(TokenType, &str)> = tokens
+            .iter()
+            .map(|t| (t.token_type.clone(), t.lexeme.as_str()))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                (TokenType::DocComment, "/// doc"),
+                (TokenType::Comment, "// plain"),
+                (TokenType::Comment, "//// rule"),
+                (TokenType::DocComment, "/** block doc */"),
+                (TokenType::Comment, "/**/"),
+                (TokenType::Comment, "/* a /* b */ */"),
+                (TokenType::Identifier, "x"),
+                (TokenType::Eof, ""),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unterminated_comment() {
+        let mut scanner = Scanner::new("x /* a /* b */\n");
+        let tokens = scanner.scan_tokens();
+        assert_eq!(tokens.len(), 3);
+        assert_eq!(tokens[1].lexeme, "/* a /* b */\n");
+        assert_eq!(tokens[1].span, Span::new(2, 15));
+        assert_eq!(
+            scanner.errors()[0],
+            ScanError {
+                kind: ScanErrorKind::UnterminatedComment,
+                line: 1,
+                column: 3,
+                span: Span::new(2, 15),
+            }
+        );
+    }
+
+    #[test]
+    fn test_string_escapes() {
+        let mut scanner = Scanner::new(r#""a\n\t\\ \"q\" \u{48}\u{1F600}""#);
+        let tokens = scanner.scan_tokens();
+        assert_eq!(tokens[0].lexeme, r#""a\n\t\\ \"q\" \u{48}\u{1F600}""#);
+        assert_eq!(
+            tokens[0].literal,
+            Literal::String("a\n\t\\ \"q\" H😀".to_string())
+        );
+        assert!(scanner.errors().is_empty());
+    }
+
+    #[test]
+    fn test_invalid_escapes() {
+        let mut scanner = Scanner::new("x \"ok\\q\\u{zz}\" y \"\\u{110000}\"");
+        let tokens = scanner.scan_tokens();
+        let lexemes: Vec<&str> = tokens.iter().map(|t| t.lexeme.as_str()).collect();
+        assert_eq!(
+            lexemes,
+            vec!["x", "\"ok\\q\\u{zz}\"", "y", "\"\\u{110000}\"", ""]
+        );
+        assert_eq!(
+            tokens[1].token_type,
+            TokenType::Error(ScanErrorKind::InvalidEscape('q'))
+        );
+        assert_eq!(
+            scanner.errors(),
+            vec![
+                ScanError {
+                    kind: ScanErrorKind::InvalidEscape('q'),
+                    line: 1,
+                    column: 6,
+                    span: Span::new(5, 7),
+                },
+                ScanError {
+                    kind: ScanErrorKind::InvalidUnicodeEscape,
+                    line: 1,
+                    column: 19,
+                    span: Span::new(18, 28),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_string_interpolation() {
+        let mut scanner = Scanner::new("\"Hello, ${name}!\" \"${a + \"x${b}\"}${ {c} }\\${d}\"");
+        let tokens = scanner.scan_tokens();
+        let found: Vec<(TokenType, &str, Literal)> = tokens
+            .iter()
+            .map(|t| (t.token_type.clone(), t.lexeme.as_str(), t.literal.clone()))
+            .collect();
+        let part = |text: &str| Literal::String(text.to_string());
+        assert_eq!(
+            found,
+            vec![
+                (TokenType::StringPart, "\"Hello, ", part("Hello, ")),
+                (TokenType::InterpolationStart, "${", Literal::None),
+                (TokenType::Identifier, "name", Literal::None),
+                (TokenType::InterpolationEnd, "}", Literal::None),
+                (TokenType::StringPart, "!\"", part("!")),
+                (TokenType::StringPart, "\"", part("")),
+                (TokenType::InterpolationStart, "${", Literal::None),
+                (TokenType::Identifier, "a", Literal::None),
+                (TokenType::Plus, "+", Literal::None),
+                (TokenType::StringPart, "\"x", part("x")),
+                (TokenType::InterpolationStart, "${", Literal::None),
+                (TokenType::Identifier, "b", Literal::None),
+                (TokenType::InterpolationEnd, "}", Literal::None),
+                (TokenType::StringPart, "\"", part("")),
+                (TokenType::InterpolationEnd, "}", Literal::None),
+                (TokenType::StringPart, "", part("")),
+                (TokenType::InterpolationStart, "${", Literal::None),
+                (TokenType::LeftBrace, "{", Literal::None),
+                (TokenType::Identifier, "c", Literal::None),
+                (TokenType::RightBrace, "}", Literal::None),
+                (TokenType::InterpolationEnd, "}", Literal::None),
+                (TokenType::StringPart, "\\${d}\"", part("${d}")),
+                (TokenType::Eof, "", Literal::None),
+            ]
+        );
+        assert!(scanner.errors().is_empty());
+    }
+
+    #[test]
+    fn test_unterminated_interpolation() {
+        let mut scanner = Scanner::new("\"a ${b");
+        let tokens = scanner.scan_tokens();
+        assert_eq!(tokens.len(), 5);
+        // the Error token is empty at the end, the error points at "${"
+        assert_eq!(tokens[3].span, Span::new(6, 6));
+        assert_eq!(tokens[4].token_type, TokenType::Eof);
+        assert_eq!(
+            scanner.errors(),
+            vec![ScanError {
+                kind: ScanErrorKind::UnterminatedInterpolation,
+                line: 1,
+                column: 4,
+                span: Span::new(3, 5),
+            }]
+        );
+
+        let mut scanner = Scanner::new("\"a ${b} c");
+        scanner.scan_tokens();
+        assert_eq!(scanner.errors()[0].kind, ScanErrorKind::UnterminatedString);
+        assert_eq!(scanner.errors()[0].span, Span::new(7, 9));
+    }
+
+    #[test]
+    fn test_numeric_literals() {
+        let mut scanner = Scanner::new("0xFF 0b1010 0o17 1e-9 2.5E+3 1_000_000 1e x 1.e3");
+        let tokens = scanner.scan_tokens();
+        let found: Vec<(&str, Literal)> = tokens
+            .iter()
+            .map(|t| (t.lexeme.as_str(), t.literal.clone()))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                ("0xFF", Literal::Number(255.0)),
+                ("0b1010", Literal::Number(10.0)),
+                ("0o17", Literal::Number(15.0)),
+                ("1e-9", Literal::Number(1e-9)),
+                ("2.5E+3", Literal::Number(2500.0)),
+                ("1_000_000", Literal::Number(1_000_000.0)),
+                ("1", Literal::Number(1.0)),
+                ("e", Literal::None),
+                ("x", Literal::None),
+                ("1", Literal::Number(1.0)),
+                (".", Literal::None),
+                ("e3", Literal::None),
+                ("", Literal::None),
+            ]
+        );
+        assert!(scanner.errors().is_empty());
+    }
+
+    #[test]
+    fn test_numeric_errors() {
+        let mut scanner = Scanner::new("0x 0b102 1__0 1e999 0xFFFF_FFFF_FFFF_FFFF_F 7");
+        let tokens = scanner.scan_tokens();
+        assert_eq!(tokens.len(), 7);
+        assert_eq!(tokens[5].lexeme, "7");
+        let errors: Vec<(ScanErrorKind, Span)> = scanner
+            .errors()
+            .iter()
+            .map(|e| (e.kind.clone(), e.span))
+            .collect();
+        assert_eq!(
+            errors,
+            vec![
+                (ScanErrorKind::MalformedNumber, Span::new(0, 2)),
+                (ScanErrorKind::MalformedNumber, Span::new(3, 8)),
+                (ScanErrorKind::MalformedNumber, Span::new(9, 13)),
+                (ScanErrorKind::NumberOutOfRange, Span::new(14, 19)),
+                (ScanErrorKind::NumberOutOfRange, Span::new(20, 43)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_integer_literals() {
+        let source = "9007199254740993 0xFF 1.5 2e3 9223372036854775808";
+        let mut scanner = Scanner::new(source);
+        let tokens = scanner.scan_tokens();
+        assert_eq!(tokens[0].token_type, TokenType::Number);
+        assert_eq!(tokens[0].literal, Literal::Number(9007199254740992.0));
+
+        let config = ScannerConfig::new().integer_literals(true);
+        let mut scanner = Scanner::with_config(source, config);
+        let tokens = scanner.scan_tokens();
+        let found: Vec<(TokenType, Literal)> = tokens
+            .iter()
+            .map(|t| (t.token_type.clone(), t.literal.clone()))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                (TokenType::Integer, Literal::Integer(9007199254740993)),
+                (TokenType::Integer, Literal::Integer(255)),
+                (TokenType::Number, Literal::Number(1.5)),
+                (TokenType::Number, Literal::Number(2000.0)),
+                (
+                    TokenType::Error(ScanErrorKind::NumberOutOfRange),
+                    Literal::None
+                ),
+                (TokenType::Eof, Literal::None),
+            ]
+        );
+        assert_eq!(scanner.errors()[0].kind, ScanErrorKind::NumberOutOfRange);
+        // an integer never compares equal to the float with the same value
+        assert_ne!(Literal::Integer(1), Literal::Number(1.0));
+        assert_eq!(tokens[1].to_string(), "Integer 0xFF Integer(255)");
+    }
 
+    #[test]
+    fn test_dialect() {
+        let dialect = Dialect::default()
+            .with_keyword("break", TokenType::Break)
+            .with_keyword("let", TokenType::Let)
+            .with_keyword("unless", TokenType::Keyword(7))
+            .without_keyword("var");
+        let config = ScannerConfig::new().dialect(dialect);
+        let mut scanner = Scanner::with_config("let var unless break while", config.clone());
+        let types: Vec<TokenType> = scanner
+            .scan_tokens()
+            .into_iter()
+            .map(|t| t.token_type)
+            .collect();
+        assert_eq!(
+            types,
+            vec![
+                TokenType::Let,
+                TokenType::Identifier,
+                TokenType::Keyword(7),
+                TokenType::Break,
+                TokenType::While,
+                TokenType::Eof,
+            ]
+        );
+        // cloned configs share the one keyword table
+        let other = config.clone();
+        assert!(Arc::ptr_eq(&config.dialect, &other.dialect));
+        assert!(Arc::ptr_eq(
+            &ScannerConfig::default().dialect,
+            &Dialect::lox()
+        ));
+    }
 
+    #[test]
+    fn test_error_tokens() {
+        let mut scanner = Scanner::new("a @# b \"c");
+        let tokens = scanner.scan_borrowed();
+        let found: Vec<(TokenType, &str)> = tokens
+            .iter()
+            .map(|t| (t.token_type.clone(), t.lexeme))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                (TokenType::Identifier, "a"),
+                (
+                    TokenType::Error(ScanErrorKind::UnexpectedCharacter('@')),
+                    "@"
+                ),
+                (
+                    TokenType::Error(ScanErrorKind::UnexpectedCharacter('#')),
+                    "#"
+                ),
+                (TokenType::Identifier, "b"),
+                (TokenType::Error(ScanErrorKind::UnterminatedString), "\"c"),
+                (TokenType::Eof, ""),
+            ]
+        );
+        assert_eq!(scanner.errors().len(), 3);
+        assert_eq!(tokens[4].span, Span::new(7, 9));
+    }
+
+    #[test]
+    fn test_single_eof() {
+        let mut scanner = Scanner::new("a\n  é\n");
+        let tokens = scanner.scan_borrowed();
+        let eof = tokens.last().unwrap();
+        assert_eq!(eof.token_type, TokenType::Eof);
+        assert_eq!((eof.line, eof.column), (3, 1));
+        assert_eq!(eof.span, Span::new(7, 7));
+        let eof_count = tokens
+            .iter()
+            .filter(|t| t.token_type == TokenType::Eof)
+            .count();
+        assert_eq!(eof_count, 1);
+        // once the Eof is out the scanner is exhausted
+        assert!(scanner.next_token().is_none());
+        assert!(scanner.next().is_none());
+        assert!(scanner.scan_borrowed().is_empty());
+
+        // the Eof also follows errors at the very end of the source
+        let mut scanner = Scanner::new("/* open");
+        let tokens = scanner.scan_borrowed();
+        assert_eq!(tokens.len(), 2);
+        assert_eq!(tokens[1].token_type, TokenType::Eof);
+        assert_eq!((tokens[1].line, tokens[1].column), (1, 8));
+    }
+
+    #[test]
+    fn test_checkpoint_restore() {
+        let mut scanner = Scanner::new("a \"x${b}y\" c @");
+        scanner.next_token();
+        scanner.next_token();
+        scanner.next_token();
+        // right after "${", one interpolation is open
+        let checkpoint = scanner.checkpoint();
+        assert_eq!(checkpoint.offset(), 6);
+        assert_eq!(checkpoint.interpolation_depth(), 1);
+        let rest: Vec<Token> = scanner.scan_tokens();
+        assert_eq!(scanner.errors().len(), 1);
+
+        scanner.restore(&checkpoint);
+        assert!(scanner.errors().is_empty());
+        assert_eq!(scanner.scan_tokens(), rest);
+        assert_eq!(rest[0].lexeme, "b");
+        assert_eq!(rest[2].token_type, TokenType::StringPart);
+    }
+
+    #[test]
+    fn test_start_inside_block_comment() {
+        let source = "/* one\n /* two\n */ still */ x\n";
+        let config = ScannerConfig::new().emit_comments(true);
+        // line 2 starts inside one comment
+        let checkpoint = Checkpoint::at(7, 2, 1, LexMode::BlockComment { depth: 1 });
+        let mut scanner = Scanner::from_checkpoint(source, config, &checkpoint);
+        let tokens = scanner.scan_tokens();
+        assert_eq!(tokens[0].token_type, TokenType::Comment);
+        assert_eq!(tokens[0].lexeme, " /* two\n */ still */");
+        assert_eq!((tokens[1].lexeme.as_str(), tokens[1].line), ("x", 3));
+        assert_eq!(scanner.mode(), LexMode::Code);
+
+        let checkpoint = Checkpoint::at(3, 1, 4, LexMode::String);
+        let mut scanner = Scanner::from_checkpoint("\"a b\" c", ScannerConfig::new(), &checkpoint);
+        let tokens = scanner.scan_tokens();
+        assert_eq!(tokens[0].token_type, TokenType::StringPart);
+        assert_eq!(tokens[0].literal, Literal::String("b".to_string()));
+        assert_eq!(tokens[1].lexeme, "c");
+    }
+
+    #[test]
+    fn test_checkpoint_outside_source() {
+        let scan_from = |source, checkpoint: Checkpoint| {
+            Scanner::from_checkpoint(source, ScannerConfig::new(), &checkpoint).scan_tokens()
+        };
+        let tokens = scan_from("", Checkpoint::at(0, 1, 1, LexMode::InterpolationStart));
+        assert_eq!(tokens.len(), 1);
+        assert_eq!(tokens[0].token_type, TokenType::Eof);
+        let tokens = scan_from("a", Checkpoint::at(0, 1, 1, LexMode::InterpolationStart));
+        assert_eq!(tokens[0].token_type, TokenType::Identifier);
+        assert_eq!(tokens[1].token_type, TokenType::Eof);
+        let tokens = scan_from("ab", Checkpoint::at(5, 1, 1, LexMode::String));
+        assert_eq!(tokens.len(), 1);
+        assert_eq!(tokens[0].span, Span::new(2, 2));
+        // inside the two bytes of 'é'
+        let tokens = scan_from("é x", Checkpoint::at(1, 1, 1, LexMode::Code));
+        assert_eq!(tokens[0].lexeme, "é");
+    }
+
+    #[test]
+    fn test_lossless() {
+        let config = ScannerConfig::new().lossless(true);
+        let source = "var a\t= \"x${ b }\";  // note\r\n/* c */\r\n\n  @ \"open";
+        let mut scanner = Scanner::with_config(source, config);
+        let tokens = scanner.scan_tokens();
+        let text: String = tokens.iter().map(|token| token.lexeme.as_str()).collect();
+        assert_eq!(text, source);
+        for pair in tokens.windows(2) {
+            assert_eq!(pair[0].span.end, pair[1].span.start);
+        }
+
+        let trivia: Vec<(TokenType, &str, usize)> = tokens
+            .iter()
+            .filter(|token| {
+                matches!(
+                    token.token_type,
+                    TokenType::Whitespace | TokenType::Newline | TokenType::Comment
+                )
+            })
+            .map(|token| (token.token_type.clone(), token.lexeme.as_str(), token.line))
+            .collect();
+        assert_eq!(
+            trivia,
+            vec![
+                (TokenType::Whitespace, " ", 1),
+                (TokenType::Whitespace, "\t", 1),
+                (TokenType::Whitespace, " ", 1),
+                (TokenType::Whitespace, " ", 1),
+                (TokenType::Whitespace, " ", 1),
+                (TokenType::Whitespace, "  ", 1),
+                (TokenType::Comment, "// note", 1),
+                (TokenType::Newline, "\r\n", 1),
+                (TokenType::Comment, "/* c */", 2),
+                (TokenType::Newline, "\r\n", 2),
+                (TokenType::Newline, "\n", 3),
+                (TokenType::Whitespace, "  ", 4),
+                (TokenType::Whitespace, " ", 4),
+            ]
+        );
+        assert_eq!(scanner.errors().len(), 2);
+    }
+
+    #[test]
+    fn test_errors() {
+        let source_str = String::from("var a = 1;\n  a ~ 2;\n\"abc");
+        let mut scanner = Scanner::new(&source_str);
+        scanner.scan_tokens();
+        let error_list = vec![
+            ScanError {
+                kind: ScanErrorKind::UnexpectedCharacter('~'),
+                line: 2,
+                column: 5,
+                span: Span::new(15, 16),
+            },
+            ScanError {
+                kind: ScanErrorKind::UnterminatedString,
+                line: 3,
+                column: 1,
+                span: Span::new(20, 24),
+            },
+        ];
+        assert_eq!(scanner.errors(), error_list);
+    }
+}
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::token::Literal;
 
     #[test]
     fn identifiers() {
-        let mut scanner = Scanner::new(String::from("andy formless fo _ _123 _abc ab123 \n abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ1234567890_"));
+        let mut scanner = Scanner::new(
+            "andy formless fo _ _123 _abc ab123 \n abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ1234567890_",
+        );
         let tokens = scanner.scan_tokens();
 
         let expected_tokens = [
@@ -329,42 +1302,56 @@ mod tests {
                 lexeme: String::from("andy"),
                 literal: Literal::None,
                 line: 1,
+                column: 1,
+                span: Span::new(0, 4),
             },
             Token {
                 token_type: TokenType::Identifier,
                 lexeme: String::from("formless"),
                 literal: Literal::None,
                 line: 1,
+                column: 6,
+                span: Span::new(5, 13),
             },
             Token {
                 token_type: TokenType::Identifier,
                 lexeme: String::from("fo"),
                 literal: Literal::None,
                 line: 1,
+                column: 15,
+                span: Span::new(14, 16),
             },
             Token {
                 token_type: TokenType::Identifier,
                 lexeme: String::from("_"),
                 literal: Literal::None,
                 line: 1,
+                column: 18,
+                span: Span::new(17, 18),
             },
             Token {
                 token_type: TokenType::Identifier,
                 lexeme: String::from("_123"),
                 literal: Literal::None,
                 line: 1,
+                column: 20,
+                span: Span::new(19, 23),
             },
             Token {
                 token_type: TokenType::Identifier,
                 lexeme: String::from("_abc"),
                 literal: Literal::None,
                 line: 1,
+                column: 25,
+                span: Span::new(24, 28),
             },
             Token {
                 token_type: TokenType::Identifier,
                 lexeme: String::from("ab123"),
                 literal: Literal::None,
                 line: 1,
+                column: 30,
+                span: Span::new(29, 34),
             },
             Token {
                 token_type: TokenType::Identifier,
@@ -373,12 +1360,16 @@ mod tests {
                 ),
                 literal: Literal::None,
                 line: 2,
+                column: 2,
+                span: Span::new(37, 100),
             },
             Token {
                 token_type: TokenType::Eof,
                 lexeme: String::new(),
                 literal: Literal::None,
                 line: 2,
+                column: 65,
+                span: Span::new(100, 100),
             },
         ];
 
@@ -390,9 +1381,8 @@ mod tests {
 
     #[test]
     fn keywords() {
-        let mut scanner = Scanner::new(String::from(
-            "and class else false for fun if nil or return super this true var while",
-        ));
+        let mut scanner =
+            Scanner::new("and class else false for fun if nil or return super this true var while");
         let tokens = scanner.scan_tokens();
 
         let expected_tokens = [
@@ -401,96 +1391,128 @@ mod tests {
                 lexeme: String::from("and"),
                 literal: Literal::None,
                 line: 1,
+                column: 1,
+                span: Span::new(0, 3),
             },
             Token {
                 token_type: TokenType::Class,
                 lexeme: String::from("class"),
                 literal: Literal::None,
                 line: 1,
+                column: 5,
+                span: Span::new(4, 9),
             },
             Token {
                 token_type: TokenType::Else,
                 lexeme: String::from("else"),
                 literal: Literal::None,
                 line: 1,
+                column: 11,
+                span: Span::new(10, 14),
             },
             Token {
                 token_type: TokenType::False,
                 lexeme: String::from("false"),
                 literal: Literal::None,
                 line: 1,
+                column: 16,
+                span: Span::new(15, 20),
             },
             Token {
                 token_type: TokenType::For,
                 lexeme: String::from("for"),
                 literal: Literal::None,
                 line: 1,
+                column: 22,
+                span: Span::new(21, 24),
             },
             Token {
                 token_type: TokenType::Fun,
                 lexeme: String::from("fun"),
                 literal: Literal::None,
                 line: 1,
+                column: 26,
+                span: Span::new(25, 28),
             },
             Token {
                 token_type: TokenType::If,
                 lexeme: String::from("if"),
                 literal: Literal::None,
                 line: 1,
+                column: 30,
+                span: Span::new(29, 31),
             },
             Token {
                 token_type: TokenType::Nil,
                 lexeme: String::from("nil"),
                 literal: Literal::None,
                 line: 1,
+                column: 33,
+                span: Span::new(32, 35),
             },
             Token {
                 token_type: TokenType::Or,
                 lexeme: String::from("or"),
                 literal: Literal::None,
                 line: 1,
+                column: 37,
+                span: Span::new(36, 38),
             },
             Token {
                 token_type: TokenType::Return,
                 lexeme: String::from("return"),
                 literal: Literal::None,
                 line: 1,
+                column: 40,
+                span: Span::new(39, 45),
             },
             Token {
                 token_type: TokenType::Super,
                 lexeme: String::from("super"),
                 literal: Literal::None,
                 line: 1,
+                column: 47,
+                span: Span::new(46, 51),
             },
             Token {
                 token_type: TokenType::This,
                 lexeme: String::from("this"),
                 literal: Literal::None,
                 line: 1,
+                column: 53,
+                span: Span::new(52, 56),
             },
             Token {
                 token_type: TokenType::True,
                 lexeme: String::from("true"),
                 literal: Literal::None,
                 line: 1,
+                column: 58,
+                span: Span::new(57, 61),
             },
             Token {
                 token_type: TokenType::Var,
                 lexeme: String::from("var"),
                 literal: Literal::None,
                 line: 1,
+                column: 63,
+                span: Span::new(62, 65),
             },
             Token {
                 token_type: TokenType::While,
                 lexeme: String::from("while"),
                 literal: Literal::None,
                 line: 1,
+                column: 67,
+                span: Span::new(66, 71),
             },
             Token {
                 token_type: TokenType::Eof,
                 lexeme: String::new(),
                 literal: Literal::None,
                 line: 1,
+                column: 72,
+                span: Span::new(71, 71),
             },
         ];
 
@@ -502,7 +1524,7 @@ mod tests {
 
     #[test]
     fn numbers() {
-        let mut scanner = Scanner::new(String::from("123\n123.456\n.456\n123."));
+        let mut scanner = Scanner::new("123\n123.456\n.456\n123.");
         let tokens = scanner.scan_tokens();
 
         let expected_tokens = [
@@ -511,42 +1533,56 @@ mod tests {
                 lexeme: String::from("123"),
                 literal: Literal::Number(123.0),
                 line: 1,
+                column: 1,
+                span: Span::new(0, 3),
             },
             Token {
                 token_type: TokenType::Number,
                 lexeme: String::from("123.456"),
                 literal: Literal::Number(123.456),
                 line: 2,
+                column: 1,
+                span: Span::new(4, 11),
             },
             Token {
                 token_type: TokenType::Dot,
                 lexeme: String::from("."),
                 literal: Literal::None,
                 line: 3,
+                column: 1,
+                span: Span::new(12, 13),
             },
             Token {
                 token_type: TokenType::Number,
                 lexeme: String::from("456"),
                 literal: Literal::Number(456.0),
                 line: 3,
+                column: 2,
+                span: Span::new(13, 16),
             },
             Token {
                 token_type: TokenType::Number,
                 lexeme: String::from("123"),
                 literal: Literal::Number(123.0),
                 line: 4,
+                column: 1,
+                span: Span::new(17, 20),
             },
             Token {
                 token_type: TokenType::Dot,
                 lexeme: String::from("."),
                 literal: Literal::None,
                 line: 4,
+                column: 4,
+                span: Span::new(20, 21),
             },
             Token {
                 token_type: TokenType::Eof,
                 lexeme: String::new(),
                 literal: Literal::None,
                 line: 4,
+                column: 5,
+                span: Span::new(21, 21),
             },
         ];
 
@@ -558,7 +1594,7 @@ mod tests {
 
     #[test]
     fn punctuators() {
-        let mut scanner = Scanner::new(String::from("(){};,+-*!===<=>=!=<>/."));
+        let mut scanner = Scanner::new("(){};,+-*!===<=>=!=<>/.");
         let tokens = scanner.scan_tokens();
 
         let expected_tokens = [
@@ -567,114 +1603,152 @@ mod tests {
                 lexeme: String::from("("),
                 literal: Literal::None,
                 line: 1,
+                column: 1,
+                span: Span::new(0, 1),
             },
             Token {
                 token_type: TokenType::RightParen,
                 lexeme: String::from(")"),
                 literal: Literal::None,
                 line: 1,
+                column: 2,
+                span: Span::new(1, 2),
             },
             Token {
                 token_type: TokenType::LeftBrace,
                 lexeme: String::from("{"),
                 literal: Literal::None,
                 line: 1,
+                column: 3,
+                span: Span::new(2, 3),
             },
             Token {
                 token_type: TokenType::RightBrace,
                 lexeme: String::from("}"),
                 literal: Literal::None,
                 line: 1,
+                column: 4,
+                span: Span::new(3, 4),
             },
             Token {
                 token_type: TokenType::Semicolon,
                 lexeme: String::from(";"),
                 literal: Literal::None,
                 line: 1,
+                column: 5,
+                span: Span::new(4, 5),
             },
             Token {
                 token_type: TokenType::Comma,
                 lexeme: String::from(","),
                 literal: Literal::None,
                 line: 1,
+                column: 6,
+                span: Span::new(5, 6),
             },
             Token {
                 token_type: TokenType::Plus,
                 lexeme: String::from("+"),
                 literal: Literal::None,
                 line: 1,
+                column: 7,
+                span: Span::new(6, 7),
             },
             Token {
                 token_type: TokenType::Minus,
                 lexeme: String::from("-"),
                 literal: Literal::None,
                 line: 1,
+                column: 8,
+                span: Span::new(7, 8),
             },
             Token {
                 token_type: TokenType::Star,
                 lexeme: String::from("*"),
                 literal: Literal::None,
                 line: 1,
+                column: 9,
+                span: Span::new(8, 9),
             },
             Token {
                 token_type: TokenType::BangEqual,
                 lexeme: String::from("!="),
                 literal: Literal::None,
                 line: 1,
+                column: 10,
+                span: Span::new(9, 11),
             },
             Token {
                 token_type: TokenType::EqualEqual,
                 lexeme: String::from("=="),
                 literal: Literal::None,
                 line: 1,
+                column: 12,
+                span: Span::new(11, 13),
             },
             Token {
                 token_type: TokenType::LessEqual,
                 lexeme: String::from("<="),
                 literal: Literal::None,
                 line: 1,
+                column: 14,
+                span: Span::new(13, 15),
             },
             Token {
                 token_type: TokenType::GreaterEqual,
                 lexeme: String::from(">="),
                 literal: Literal::None,
                 line: 1,
+                column: 16,
+                span: Span::new(15, 17),
             },
             Token {
                 token_type: TokenType::BangEqual,
                 lexeme: String::from("!="),
                 literal: Literal::None,
                 line: 1,
+                column: 18,
+                span: Span::new(17, 19),
             },
             Token {
                 token_type: TokenType::Less,
                 lexeme: String::from("<"),
                 literal: Literal::None,
                 line: 1,
+                column: 20,
+                span: Span::new(19, 20),
             },
             Token {
                 token_type: TokenType::Greater,
                 lexeme: String::from(">"),
                 literal: Literal::None,
                 line: 1,
+                column: 21,
+                span: Span::new(20, 21),
             },
             Token {
                 token_type: TokenType::Slash,
                 lexeme: String::from("/"),
                 literal: Literal::None,
                 line: 1,
+                column: 22,
+                span: Span::new(21, 22),
             },
             Token {
                 token_type: TokenType::Dot,
                 lexeme: String::from("."),
                 literal: Literal::None,
                 line: 1,
+                column: 23,
+                span: Span::new(22, 23),
             },
             Token {
                 token_type: TokenType::Eof,
                 lexeme: String::from(""),
                 literal: Literal::None,
                 line: 1,
+                column: 24,
+                span: Span::new(23, 23),
             },
         ];
 
@@ -686,7 +1760,7 @@ mod tests {
 
     #[test]
     fn strings() {
-        let mut scanner = Scanner::new(String::from("\"\" \n \"string\""));
+        let mut scanner = Scanner::new("\"\" \n \"string\"");
         let tokens = scanner.scan_tokens();
 
         let expected_tokens = [
@@ -695,18 +1769,24 @@ mod tests {
                 lexeme: String::from("\"\""),
                 literal: Literal::String(String::from("")),
                 line: 1,
+                column: 1,
+                span: Span::new(0, 2),
             },
             Token {
                 token_type: TokenType::String,
                 lexeme: String::from("\"string\""),
                 literal: Literal::String(String::from("string")),
                 line: 2,
+                column: 2,
+                span: Span::new(5, 13),
             },
             Token {
                 token_type: TokenType::Eof,
                 lexeme: String::from(""),
                 literal: Literal::None,
                 line: 2,
+                column: 10,
+                span: Span::new(13, 13),
             },
         ];
 
@@ -718,14 +1798,14 @@ mod tests {
 
     #[test]
     fn whitespace() {
-        let mut scanner = Scanner::new(String::from(
+        let mut scanner = Scanner::new(
             "space    tabs				newlines
 
 
 
 
         end",
-        ));
+        );
         let tokens = scanner.scan_tokens();
 
         let expected_tokens = [
@@ -734,30 +1814,40 @@ mod tests {
                 lexeme: String::from("space"),
                 literal: Literal::None,
                 line: 1,
+                column: 1,
+                span: Span::new(0, 5),
             },
             Token {
                 token_type: TokenType::Identifier,
                 lexeme: String::from("tabs"),
                 literal: Literal::None,
                 line: 1,
+                column: 10,
+                span: Span::new(9, 13),
             },
             Token {
                 token_type: TokenType::Identifier,
                 lexeme: String::from("newlines"),
                 literal: Literal::None,
                 line: 1,
+                column: 18,
+                span: Span::new(17, 25),
             },
             Token {
                 token_type: TokenType::Identifier,
                 lexeme: String::from("end"),
                 literal: Literal::None,
                 line: 6,
+                column: 9,
+                span: Span::new(38, 41),
             },
             Token {
                 token_type: TokenType::Eof,
                 lexeme: String::from(""),
                 literal: Literal::None,
                 line: 6,
+                column: 12,
+                span: Span::new(41, 41),
             },
         ];
 
@@ -767,4 +1857,3 @@ mod tests {
         }
     }
 }
-
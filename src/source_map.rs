@@ -0,0 +1,206 @@
+use crate::span::{SourceId, Span};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+// one loaded script, with the offsets its lines start at
+#[derive(Debug, PartialEq, Clone)]
+pub struct SourceFile {
+    id: SourceId,
+    path: PathBuf,
+    text: String,
+    line_starts: Vec<usize>,
+}
+
+impl SourceFile {
+    fn new(id: SourceId, path: PathBuf, text: String) -> Self {
+        let line_starts = std::iter::once(0)
+            .chain(text.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+        SourceFile {
+            id,
+            path,
+            text,
+            line_starts,
+        }
+    }
+
+    pub fn id(&self) -> SourceId {
+        self.id
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn line_count(&self) -> usize {
+        self.line_starts.len()
+    }
+
+    // 1-based line and column of a byte offset, counting columns in chars the
+    // way the scanner does; None when the offset is past the end or inside a char
+    pub fn line_column(&self, offset: usize) -> Option<(usize, usize)> {
+        if !self.text.is_char_boundary(offset) {
+            return None;
+        }
+        let line = self.line_starts.partition_point(|&start| start <= offset);
+        let line_start = self.line_starts[line - 1];
+        let column = self.text[line_start..offset].chars().count() + 1;
+        Some((line, column))
+    }
+
+    // the text of a 1-based line, without its "\n" or "\r\n"
+    pub fn line(&self, line: usize) -> Option<&str> {
+        let start = *self.line_starts.get(line.checked_sub(1)?)?;
+        let end = match self.line_starts.get(line) {
+            Some(&next) => next - 1,
+            None => self.text.len(),
+        };
+        let text = &self.text[start..end];
+        Some(text.strip_suffix('\r').unwrap_or(text))
+    }
+
+    // byte offset of the start of a 1-based line
+    pub fn line_start(&self, line: usize) -> Option<usize> {
+        self.line_starts.get(line.checked_sub(1)?).copied()
+    }
+}
+
+// where a span starts, in terms a person reading the file understands
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Location<'a> {
+    pub path: &'a Path,
+    pub line: usize,
+    pub column: usize,
+}
+
+// owns every script the scanner and later stages look at, handing each a
+// SourceId that spans carry around
+#[derive(Debug, Default, Clone)]
+pub struct SourceMap {
+    files: Vec<SourceFile>,
+}
+
+impl SourceMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&mut self, path: impl Into<PathBuf>, text: impl Into<String>) -> SourceId {
+        let id = SourceId(self.files.len() as u32);
+        self.files
+            .push(SourceFile::new(id, path.into(), text.into()));
+        id
+    }
+
+    // read a script from disk; loading the same path twice gives two ids
+    pub fn load(&mut self, path: impl AsRef<Path>) -> io::Result<SourceId> {
+        let path = path.as_ref();
+        let text = fs::read_to_string(path)?;
+        Ok(self.add(path, text))
+    }
+
+    pub fn get(&self, id: SourceId) -> Option<&SourceFile> {
+        self.files.get(id.index())
+    }
+
+    pub fn find(&self, path: impl AsRef<Path>) -> Option<SourceId> {
+        let path = path.as_ref();
+        self.files
+            .iter()
+            .find(|file| file.path == path)
+            .map(|file| file.id)
+    }
+
+    pub fn files(&self) -> impl Iterator<Item = &SourceFile> {
+        self.files.iter()
+    }
+
+    pub fn resolve(&self, span: Span) -> Option<Location<'_>> {
+        let file = self.get(span.source)?;
+        let (line, column) = file.line_column(span.start)?;
+        Some(Location {
+            path: &file.path,
+            line,
+            column,
+        })
+    }
+
+    // the whole line the span starts on
+    pub fn line_text(&self, span: Span) -> Option<&str> {
+        let file = self.get(span.source)?;
+        let (line, _) = file.line_column(span.start)?;
+        file.line(line)
+    }
+
+    // the text the span covers
+    pub fn snippet(&self, span: Span) -> Option<&str> {
+        self.get(span.source)?.text.get(span.range())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ScannerConfig;
+    use crate::scanner::Scanner;
+    use crate::token::TokenType;
+
+    #[test]
+    fn resolves_spans_across_files() {
+        let mut map = SourceMap::new();
+        let main = map.add("main.lox", "print 1;\r\nvar é = \"x\";\n");
+        let lib = map.add("lib/util.lox", "fun f() {}\n@");
+        assert_eq!(main, SourceId::default());
+        assert_eq!(map.find("lib/util.lox"), Some(lib));
+        assert_eq!(map.find("other.lox"), None);
+
+        let source = map.get(main).unwrap().text();
+        let tokens = Scanner::new(source).scan_tokens();
+        let string = tokens
+            .iter()
+            .find(|token| token.token_type == TokenType::String)
+            .unwrap();
+        let location = map.resolve(string.span).unwrap();
+        assert_eq!(location.path, Path::new("main.lox"));
+        assert_eq!(
+            (location.line, location.column),
+            (string.line, string.column)
+        );
+        assert_eq!((location.line, location.column), (2, 9));
+        assert_eq!(map.line_text(string.span), Some("var é = \"x\";"));
+        assert_eq!(map.snippet(string.span), Some("\"x\""));
+
+        let source = map.get(lib).unwrap().text();
+        let mut scanner = Scanner::with_config(source, ScannerConfig::new()).source_id(lib);
+        scanner.scan_tokens();
+        let error = &scanner.errors()[0];
+        assert_eq!(error.span.source, lib);
+        let location = map.resolve(error.span).unwrap();
+        assert_eq!(location.path, Path::new("lib/util.lox"));
+        assert_eq!((location.line, location.column), (2, 1));
+        assert_eq!(map.line_text(error.span), Some("@"));
+    }
+
+    #[test]
+    fn lines() {
+        let mut map = SourceMap::new();
+        let id = map.add("a.lox", "one\n\ntwo");
+        let file = map.get(id).unwrap();
+        assert_eq!(file.line_count(), 3);
+        assert_eq!(file.line(1), Some("one"));
+        assert_eq!(file.line(2), Some(""));
+        assert_eq!(file.line(3), Some("two"));
+        assert_eq!(file.line(0), None);
+        assert_eq!(file.line(4), None);
+        assert_eq!(file.line_start(3), Some(5));
+        assert_eq!(file.line_column(4), Some((2, 1)));
+        assert_eq!(file.line_column(8), Some((3, 4)));
+        assert_eq!(file.line_column(9), None);
+        assert_eq!(map.resolve(Span::new(0, 1).in_source(SourceId(7))), None);
+    }
+}
@@ -0,0 +1,68 @@
+use std::fmt;
+use std::ops::Range;
+
+// which file of a SourceMap a span points into; the default id is the first
+// file added, so single-file users never have to think about it
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default, Hash, PartialOrd, Ord)]
+pub struct SourceId(pub(crate) u32);
+
+impl SourceId {
+    pub fn index(&self) -> usize {
+        self.0 as usize
+    }
+}
+
+impl fmt::Display for SourceId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "#{}", self.0)
+    }
+}
+
+// a half-open range of byte offsets into the scanned source
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default, Hash)]
+pub struct Span {
+    pub source: SourceId,
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    pub fn new(start: usize, end: usize) -> Self {
+        Span {
+            source: SourceId::default(),
+            start,
+            end,
+        }
+    }
+
+    // the same range, but in the given file
+    pub fn in_source(self, source: SourceId) -> Self {
+        Span { source, ..self }
+    }
+
+    // from the start of this span to the end of a later one
+    pub fn to(self, end: Span) -> Self {
+        Span {
+            end: end.end,
+            ..self
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.end - self.start
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
+
+    pub fn range(&self) -> Range<usize> {
+        self.start..self.end
+    }
+}
+
+impl From<Range<usize>> for Span {
+    fn from(range: Range<usize>) -> Self {
+        Span::new(range.start, range.end)
+    }
+}
@@ -0,0 +1,116 @@
+use crate::token::{Token, TokenType};
+
+// a cursor for parsers over a scanned token list; the list always ends in
+// exactly one Eof, so the cursor can stop on it instead of running off the end
+#[derive(Debug, Clone)]
+pub struct TokenStream<'t> {
+    tokens: &'t [Token],
+    current: usize,
+}
+
+impl<'t> TokenStream<'t> {
+    // panics unless `tokens` ends with its only Eof, as every scanner output does
+    pub fn new(tokens: &'t [Token]) -> Self {
+        let eof_count = tokens
+            .iter()
+            .filter(|token| token.token_type == TokenType::Eof)
+            .count();
+        assert!(
+            eof_count == 1
+                && tokens
+                    .last()
+                    .is_some_and(|t| t.token_type == TokenType::Eof),
+            "a token stream must end with exactly one Eof token"
+        );
+        TokenStream { tokens, current: 0 }
+    }
+
+    pub fn tokens(&self) -> &'t [Token] {
+        self.tokens
+    }
+
+    pub fn position(&self) -> usize {
+        self.current
+    }
+
+    pub fn set_position(&mut self, position: usize) {
+        self.current = position.min(self.tokens.len() - 1);
+    }
+
+    // the token under the cursor, the Eof once everything is consumed
+    pub fn peek(&self) -> &'t Token {
+        &self.tokens[self.current]
+    }
+
+    // the token after the cursor, staying on the Eof at the end
+    pub fn peek_next(&self) -> &'t Token {
+        &self.tokens[(self.current + 1).min(self.tokens.len() - 1)]
+    }
+
+    pub fn previous(&self) -> Option<&'t Token> {
+        self.current.checked_sub(1).map(|index| &self.tokens[index])
+    }
+
+    pub fn is_at_end(&self) -> bool {
+        self.peek().token_type == TokenType::Eof
+    }
+
+    // hand out the current token and move on, except past the Eof
+    pub fn advance(&mut self) -> &'t Token {
+        let token = self.peek();
+        if !self.is_at_end() {
+            self.current += 1;
+        }
+        token
+    }
+
+    pub fn check(&self, token_type: &TokenType) -> bool {
+        self.peek().token_type == *token_type
+    }
+
+    // consume the current token if it is any of `token_types`
+    pub fn next_if(&mut self, token_types: &[TokenType]) -> Option<&'t Token> {
+        match token_types.iter().any(|token_type| self.check(token_type)) {
+            true => Some(self.advance()),
+            false => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scan;
+
+    #[test]
+    fn cursor_stops_on_eof() {
+        let tokens = scan("a + b").unwrap();
+        let mut stream = TokenStream::new(&tokens);
+        assert!(stream.previous().is_none());
+        assert_eq!(stream.peek_next().token_type, TokenType::Plus);
+        assert_eq!(stream.advance().lexeme, "a");
+        assert!(
+            stream
+                .next_if(&[TokenType::Minus, TokenType::Plus])
+                .is_some()
+        );
+        assert!(stream.next_if(&[TokenType::Minus]).is_none());
+        assert_eq!(stream.advance().lexeme, "b");
+        assert!(stream.is_at_end());
+        assert_eq!(stream.advance().token_type, TokenType::Eof);
+        assert_eq!(stream.advance().token_type, TokenType::Eof);
+        assert_eq!(stream.peek_next().token_type, TokenType::Eof);
+        assert_eq!(stream.position(), 3);
+        assert_eq!(stream.previous().unwrap().lexeme, "b");
+        stream.set_position(0);
+        assert!(stream.check(&TokenType::Identifier));
+    }
+
+    #[test]
+    #[should_panic(expected = "exactly one Eof")]
+    fn requires_eof() {
+        let mut tokens = scan("a").unwrap();
+        tokens.pop();
+        TokenStream::new(&tokens);
+    }
+}
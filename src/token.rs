@@ -1,18 +1,22 @@
+use crate::error::ScanErrorKind;
+use crate::escape::unescape;
+use crate::number::{parse_integer, parse_number};
+use crate::span::Span;
+use std::borrow::Cow;
 use std::fmt;
 
-
-
 #[derive(Debug, PartialEq, Clone)]
-pub enum Literal{
+pub enum Literal {
     String(String),
+    // every number in plain Lox; floats keep this kind when integers are enabled
     Number(f64),
+    Integer(i64),
     Bool(bool),
-    None
+    None,
 }
 
-
 #[derive(Debug, PartialEq, Clone)]
-pub enum TokenType{
+pub enum TokenType {
     // Single-character tokens
     LeftParen,
     RightParen,
@@ -39,6 +43,12 @@ pub enum TokenType{
     Identifier,
     String,
     Number,
+    // only when the scanner is configured with integer_literals
+    Integer,
+    // "a ${b} c" is StringPart InterpolationStart Identifier InterpolationEnd StringPart
+    StringPart,
+    InterpolationStart,
+    InterpolationEnd,
 
     // Keywords
     And,
@@ -57,16 +67,111 @@ pub enum TokenType{
     True,
     Var,
     While,
+    // Keywords outside plain Lox, only produced by a Dialect that adds them
+    Break,
+    Continue,
+    Const,
+    Import,
+    Let,
+    // any other dialect keyword, told apart by an id the embedder chooses
+    Keyword(u16),
+
+    // Trivia, only produced when the scanner is asked to keep comments, or
+    // whitespace as well in lossless mode
+    Comment,
+    DocComment,
+    Whitespace,
+    Newline,
+
+    // Input the scanner could not make sense of; the lexeme is the text it skipped
+    Error(ScanErrorKind),
 
-    Eof,    
+    Eof,
+}
+
+// how a token type is named in messages such as "expected ')'"
+impl fmt::Display for TokenType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let text = match self {
+            TokenType::LeftParen => "'('",
+            TokenType::RightParen => "')'",
+            TokenType::LeftBrace => "'{'",
+            TokenType::RightBrace => "'}'",
+            TokenType::Comma => "','",
+            TokenType::Dot => "'.'",
+            TokenType::Minus => "'-'",
+            TokenType::Plus => "'+'",
+            TokenType::Semicolon => "';'",
+            TokenType::Star => "'*'",
+            TokenType::Bang => "'!'",
+            TokenType::BangEqual => "'!='",
+            TokenType::Equal => "'='",
+            TokenType::EqualEqual => "'=='",
+            TokenType::Greater => "'>'",
+            TokenType::GreaterEqual => "'>='",
+            TokenType::Less => "'<'",
+            TokenType::LessEqual => "'<='",
+            TokenType::Slash => "'/'",
+            TokenType::Identifier => "identifier",
+            TokenType::String => "string",
+            TokenType::Number | TokenType::Integer => "number",
+            TokenType::StringPart => "string",
+            TokenType::InterpolationStart => "'${'",
+            TokenType::InterpolationEnd => "'}'",
+            TokenType::And => "'and'",
+            TokenType::Class => "'class'",
+            TokenType::Else => "'else'",
+            TokenType::False => "'false'",
+            TokenType::Fun => "'fun'",
+            TokenType::For => "'for'",
+            TokenType::Nil => "'nil'",
+            TokenType::If => "'if'",
+            TokenType::Print => "'print'",
+            TokenType::Or => "'or'",
+            TokenType::Return => "'return'",
+            TokenType::Super => "'super'",
+            TokenType::This => "'this'",
+            TokenType::True => "'true'",
+            TokenType::Var => "'var'",
+            TokenType::While => "'while'",
+            TokenType::Break => "'break'",
+            TokenType::Continue => "'continue'",
+            TokenType::Const => "'const'",
+            TokenType::Import => "'import'",
+            TokenType::Let => "'let'",
+            TokenType::Keyword(_) => "keyword",
+            TokenType::Comment | TokenType::DocComment => "comment",
+            TokenType::Whitespace => "whitespace",
+            TokenType::Newline => "newline",
+            TokenType::Error(_) => "invalid token",
+            TokenType::Eof => "end of file",
+        };
+        write!(f, "{}", text)
+    }
 }
 
 #[derive(Debug, PartialEq, Clone)]
-pub struct Token{
-    pub token_type:TokenType,
-    pub lexeme:String,
-    pub literal:Literal,
-    pub line:usize
+pub struct Token {
+    pub token_type: TokenType,
+    pub lexeme: String,
+    pub literal: Literal,
+    // 1-based line and column of the first character of the lexeme
+    pub line: usize,
+    pub column: usize,
+    pub span: Span,
+}
+
+impl Token {
+    // line and column right after the lexeme
+    pub(crate) fn end_position(&self) -> (usize, usize) {
+        match self.lexeme.rfind('\n') {
+            Some(index) => (
+                self.line + self.lexeme.matches('\n').count(),
+                self.lexeme[index + 1..].chars().count() + 1,
+            ),
+            None => (self.line, self.column + self.lexeme.chars().count()),
+        }
+    }
 }
 
 impl fmt::Display for Token {
@@ -77,5 +182,87 @@ impl fmt::Display for Token {
             self.token_type, self.lexeme, self.literal
         )
     }
+}
+
+// a token whose lexeme is borrowed straight from the scanned source;
+// the literal value is only decoded when asked for
+#[derive(Debug, PartialEq, Clone)]
+pub struct BorrowedToken<'src> {
+    pub token_type: TokenType,
+    pub lexeme: &'src str,
+    pub line: usize,
+    pub column: usize,
+    pub span: Span,
+}
+
+impl<'src> BorrowedToken<'src> {
+    pub fn literal(&self) -> Literal {
+        match self.token_type {
+            TokenType::Number => parse_number(self.lexeme).map_or(Literal::None, Literal::Number),
+            TokenType::Integer => {
+                parse_integer(self.lexeme).map_or(Literal::None, Literal::Integer)
+            }
+            TokenType::String | TokenType::StringPart => self
+                .string_value()
+                .map_or(Literal::None, |value| Literal::String(value.into_owned())),
+            _ => Literal::None,
+        }
+    }
+
+    // the decoded value of a string token, still borrowed from the source
+    // unless it contains escape sequences
+    pub fn string_value(&self) -> Option<Cow<'src, str>> {
+        match self.token_type {
+            // drop the surrounding quotes
+            TokenType::String => Some(unescape(&self.lexeme[1..self.lexeme.len() - 1])),
+            TokenType::StringPart => Some(unescape(string_part_body(self.lexeme))),
+            _ => None,
+        }
+    }
+
+    pub fn to_token(&self) -> Token {
+        Token {
+            token_type: self.token_type.clone(),
+            lexeme: self.lexeme.to_string(),
+            literal: self.literal(),
+            line: self.line,
+            column: self.column,
+            span: self.span,
+        }
+    }
+}
+
+// the first part of an interpolated string starts with its opening quote and
+// the last one ends with the closing quote; a part in between has neither
+fn string_part_body(lexeme: &str) -> &str {
+    let body = lexeme.strip_prefix('"').unwrap_or(lexeme);
+    match body.strip_suffix('"') {
+        // the quote only closes the string if the backslashes before it pair up
+        Some(rest) if (rest.len() - rest.trim_end_matches('\\').len()) % 2 == 0 => rest,
+        _ => body,
+    }
+}
+
+impl From<&BorrowedToken<'_>> for Token {
+    fn from(token: &BorrowedToken<'_>) -> Self {
+        token.to_token()
+    }
+}
 
+impl From<BorrowedToken<'_>> for Token {
+    fn from(token: BorrowedToken<'_>) -> Self {
+        token.to_token()
+    }
+}
+
+impl fmt::Display for BorrowedToken<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:?} {} {:?}",
+            self.token_type,
+            self.lexeme,
+            self.literal()
+        )
+    }
 }
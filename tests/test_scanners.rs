@@ -0,0 +1,89 @@
+use rlox_lib::{
+    Literal, ScanErrorKind, Scanner, Span, Stmt, Token, TokenStream, TokenType, parse_program, scan,
+};
+
+#[test]
+fn scan_statement() {
+    let tokens = scan("var answer = 42;").unwrap();
+    let types: Vec<TokenType> = tokens.iter().map(|t| t.token_type.clone()).collect();
+    assert_eq!(
+        types,
+        vec![
+            TokenType::Var,
+            TokenType::Identifier,
+            TokenType::Equal,
+            TokenType::Number,
+            TokenType::Semicolon,
+            TokenType::Eof,
+        ]
+    );
+    assert_eq!(tokens[3].literal, Literal::Number(42.0));
+    assert_eq!(tokens[3].span, Span::new(13, 15));
+    assert_eq!(tokens[3].column, 14);
+    assert_eq!(&"var answer = 42;"[tokens[3].span.range()], "42");
+}
+
+#[test]
+fn scan_empty_source() {
+    let tokens = scan("").unwrap();
+    assert_eq!(
+        tokens,
+        vec![Token {
+            token_type: TokenType::Eof,
+            lexeme: String::new(),
+            literal: Literal::None,
+            line: 1,
+            column: 1,
+            span: Span::new(0, 0),
+        }]
+    );
+}
+
+#[test]
+fn scan_reports_all_errors() {
+    let errors = scan("@\n#\n\"open").unwrap_err();
+    let lines: Vec<usize> = errors.iter().map(|e| e.line).collect();
+    assert_eq!(lines, vec![1, 2, 3]);
+    assert_eq!(errors[0].kind, ScanErrorKind::UnexpectedCharacter('@'));
+    assert_eq!(errors[1].kind, ScanErrorKind::UnexpectedCharacter('#'));
+    assert_eq!(errors[2].kind, ScanErrorKind::UnterminatedString);
+    assert_eq!(errors[2].span, Span::new(4, 9));
+    assert_eq!(
+        errors.to_string(),
+        "1:1: Unexpected character '@'.\n2:1: Unexpected character '#'.\n3:1: Unterminated string."
+    );
+}
+
+#[test]
+fn scanner_is_usable_directly() {
+    let mut scanner = Scanner::new("print \"hi\";");
+    let tokens = scanner.scan_tokens();
+    assert_eq!(tokens.len(), 4);
+    assert_eq!(tokens[1].literal, Literal::String(String::from("hi")));
+    assert!(scanner.errors().is_empty());
+}
+
+#[test]
+fn token_stream_ends_on_eof() {
+    let tokens = scan("print 1;\n").unwrap();
+    let mut stream = TokenStream::new(&tokens);
+    let mut seen = vec![];
+    while !stream.is_at_end() {
+        seen.push(stream.advance().lexeme.clone());
+    }
+    assert_eq!(seen, vec!["print", "1", ";"]);
+    assert_eq!(stream.peek().line, 2);
+    assert_eq!(stream.peek().span, Span::new(9, 9));
+}
+
+#[test]
+fn scanned_program_parses() {
+    let source = "class Greeter {\n  greet(name) {\n    print \"Hello, ${name}!\";\n  }\n}\nGreeter().greet(\"Lox\");\n";
+    let statements = parse_program(&scan(source).unwrap()).unwrap();
+    assert_eq!(statements.len(), 2);
+    assert!(matches!(&statements[0], Stmt::Class { methods, .. } if methods.len() == 1));
+    assert_eq!(
+        statements[1].to_string(),
+        "(; (call (. (call Greeter) greet) \"Lox\"))"
+    );
+}